@@ -1,9 +1,10 @@
 mod performance;
 mod winrate;
 
+use crate::error::ApolloError;
 use clap::ArgMatches;
 
-pub fn main(matches: &ArgMatches) -> Result<(), String> {
+pub fn main(matches: &ArgMatches) -> Result<(), ApolloError> {
     if let Some(m) = matches.subcommand_matches("performance") {
         performance::main(m)?;
     } else if let Some(m) = matches.subcommand_matches("winrate") {
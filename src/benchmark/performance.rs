@@ -1,32 +1,32 @@
-use crate::{common::*, othello::Disc, solve::Search};
+use crate::{common::*, error::ApolloError, othello::Othello, solve::Search};
 use clap::ArgMatches;
 use rand::{thread_rng, Rng};
-use std::time::SystemTime;
 
-pub fn main(matches: &ArgMatches) -> Result<(), String> {
+pub fn main(matches: &ArgMatches) -> Result<(), ApolloError> {
     let n = matches
         .value_of("number")
         .unwrap_or("50")
-        .parse::<usize>()
-        .map_err(|e| e.to_string())?;
-
-    let depth = matches
-        .value_of("depth")
-        .unwrap_or("7")
-        .parse::<usize>()
-        .map_err(|e| e.to_string())?;
-
-    let lower = matches
-        .value_of("lower")
-        .unwrap_or("5")
-        .parse::<usize>()
-        .map_err(|e| e.to_string())?;
-
-    let upper = matches
-        .value_of("upper")
-        .unwrap_or("40")
-        .parse::<usize>()
-        .map_err(|e| e.to_string())?;
+        .parse::<usize>()?;
+
+    let depth = matches.value_of("depth").unwrap_or("7").parse::<usize>()?;
+
+    let lower = matches.value_of("lower").unwrap_or("5").parse::<usize>()?;
+
+    let upper = matches.value_of("upper").unwrap_or("40").parse::<usize>()?;
+
+    if lower >= upper {
+        return Err(ApolloError::Invalid(format!(
+            "The lower bound on random moves ({}) must be less than the upper bound ({})!",
+            lower, upper
+        )));
+    }
+
+    let start = extract_game(
+        matches.value_of("position"),
+        matches.value_of("size"),
+        matches.value_of("rules"),
+        matches.value_of("blocked"),
+    )?;
 
     let mut algs = Vec::new();
     let alg_strs = matches.values_of("algorithms").unwrap();
@@ -42,6 +42,15 @@ pub fn main(matches: &ArgMatches) -> Result<(), String> {
     for alg_str in alg_strs.clone() {
         print!("time - {},", alg_str);
     }
+    for alg_str in alg_strs.clone() {
+        print!("tt hits - {},", alg_str);
+    }
+    for alg_str in alg_strs.clone() {
+        print!("tt cutoffs - {},", alg_str);
+    }
+    for alg_str in alg_strs.clone() {
+        print!("researches - {},", alg_str);
+    }
 
     eprintln!(
         "Benchmarking performance of algorithms {:?}",
@@ -51,16 +60,19 @@ pub fn main(matches: &ArgMatches) -> Result<(), String> {
     eprintln!(" maximum depth: {}", depth);
     eprintln!(" lower bound on random moves: {}", lower);
     eprintln!(" upper bound on random moves: {}", upper);
+    eprintln!(" starting position: {}", start);
+    eprintln!(" rules: {}", start.rules());
 
-    println!("");
+    println!();
 
-    performance(&mut algs, n, depth, lower, upper);
+    performance(&mut algs, &start, n, depth, lower, upper);
 
     Ok(())
 }
 
 fn performance(
     algs: &mut Vec<Box<dyn Search>>,
+    start: &Othello,
     n: usize,
     max_depth: usize,
     lower: usize,
@@ -74,23 +86,21 @@ fn performance(
         let mut expandeds = (0..algs.len()).map(|_| 0.).collect::<Vec<_>>();
         let mut generateds = (0..algs.len()).map(|_| 0.).collect::<Vec<_>>();
         let mut times = (0..algs.len()).map(|_| 0.).collect::<Vec<_>>();
+        let mut hits = (0..algs.len()).map(|_| 0.).collect::<Vec<_>>();
+        let mut cutoffs = (0..algs.len()).map(|_| 0.).collect::<Vec<_>>();
+        let mut researches = (0..algs.len()).map(|_| 0.).collect::<Vec<_>>();
         for _ in 0..n {
             let num_moves = lower + rng.gen::<usize>() % (upper - lower);
-            let game = random_game(num_moves);
+            let game = random_game(start, num_moves);
 
             for (i, alg) in algs.iter_mut().enumerate() {
-                let start = SystemTime::now();
-                alg.search(&game, Disc::Black, depth);
-
-                let expanded = alg.nodes_expanded();
-                expandeds[i] += expanded as f64;
-
-                let generated = alg.nodes_generated();
-                generateds[i] += generated as f64;
-
-                let elapsed = start.elapsed().unwrap();
-                let ms = elapsed.subsec_millis();
-                times[i] += ms as f64;
+                let result = alg.search(&game, depth);
+                expandeds[i] += result.expanded as f64;
+                generateds[i] += result.generated as f64;
+                times[i] += result.elapsed.as_secs_f64() * 1000.;
+                hits[i] += result.tt_hits as f64;
+                cutoffs[i] += result.tt_cutoffs as f64;
+                researches[i] += result.researches as f64;
             }
         }
 
@@ -109,7 +119,22 @@ fn performance(
             print!("{},", time);
         }
 
-        println!("");
+        for &sum in hits.iter() {
+            let hit = sum / n as f64;
+            print!("{},", hit);
+        }
+
+        for &sum in cutoffs.iter() {
+            let cutoff = sum / n as f64;
+            print!("{},", cutoff);
+        }
+
+        for &sum in researches.iter() {
+            let research = sum / n as f64;
+            print!("{},", research);
+        }
+
+        println!();
 
         depths.push((expandeds, times));
     }
@@ -1,24 +1,38 @@
-use crate::{common::*, solve::Search};
+use crate::{
+    common::*,
+    error::ApolloError,
+    othello::{GgfGame, Othello},
+    solve::Search,
+};
 use clap::ArgMatches;
 
-pub fn main(matches: &ArgMatches) -> Result<(), String> {
+pub fn main(matches: &ArgMatches) -> Result<(), ApolloError> {
     let n = matches
         .value_of("number")
         .unwrap_or("100")
-        .parse::<usize>()
-        .map_err(|e| e.to_string())?;
+        .parse::<usize>()?;
 
-    let lower = matches
-        .value_of("lower")
-        .unwrap_or("5")
-        .parse::<usize>()
-        .map_err(|e| e.to_string())?;
+    let lower = matches.value_of("lower").unwrap_or("5").parse::<usize>()?;
 
-    let upper = matches
-        .value_of("upper")
-        .unwrap_or("40")
-        .parse::<usize>()
-        .map_err(|e| e.to_string())?;
+    let upper = matches.value_of("upper").unwrap_or("40").parse::<usize>()?;
+
+    if lower >= upper {
+        return Err(ApolloError::Invalid(format!(
+            "The lower bound on random moves ({}) must be less than the upper bound ({})!",
+            lower, upper
+        )));
+    }
+
+    let start = extract_game(
+        matches.value_of("position"),
+        matches.value_of("size"),
+        matches.value_of("rules"),
+        matches.value_of("blocked"),
+    )?;
+    if matches.value_of("ggf").is_some() {
+        // Fail before playing any games that couldn't be recorded
+        GgfGame::new(&start, Vec::new())?;
+    }
 
     let mut names = Vec::new();
     let mut algs = Vec::new();
@@ -33,24 +47,39 @@ pub fn main(matches: &ArgMatches) -> Result<(), String> {
     eprintln!(" number of trials: {}", n);
     eprintln!(" lower bound on random moves: {}", lower);
     eprintln!(" upper bound on random moves: {}", upper);
+    eprintln!(" starting position: {}", start);
+    eprintln!(" rules: {}", start.rules());
 
     print!(",");
     for name in names.iter() {
         print!("{},", name);
     }
-    println!("");
+    println!();
+
+    let mut games = Vec::new();
+    let records = winrate_all(&mut algs, &names, &start, n, lower, upper, &mut games)?;
 
-    let winrates = winrate_all(&mut algs, n, lower, upper);
+    if let Some(path) = matches.value_of("ggf") {
+        append_ggf(path, &games)?;
+    }
 
-    for (index, row) in winrates.iter().enumerate() {
+    // Each cell is the row's wins/draws/losses against the column,
+    // followed by its average final disc differential
+    for (index, row) in records.iter().enumerate() {
         print!("{},", names[index]);
-        for winrate in row.iter() {
-            match winrate {
-                Some(rate) => print!("{},", rate),
+        for record in row.iter() {
+            match record {
+                Some(record) => print!(
+                    "{}/{}/{} {:+.2},",
+                    record.wins,
+                    record.draws,
+                    record.losses,
+                    record.average_differential()
+                ),
                 None => print!(","),
             }
         }
-        println!("");
+        println!();
     }
 
     Ok(())
@@ -58,11 +87,14 @@ pub fn main(matches: &ArgMatches) -> Result<(), String> {
 
 fn winrate_all(
     algs: &mut Vec<(Box<dyn Search>, usize)>,
+    names: &[&str],
+    start: &Othello,
     n: usize,
     lower: usize,
     upper: usize,
-) -> Vec<Vec<Option<f64>>> {
-    let mut winrates: Vec<Vec<Option<f64>>> = (0..algs.len())
+    games: &mut Vec<GgfGame>,
+) -> Result<Vec<Vec<Option<Record>>>, ApolloError> {
+    let mut records: Vec<Vec<Option<Record>>> = (0..algs.len())
         .map(|_| (0..algs.len()).map(|_| None).collect())
         .collect();
 
@@ -71,12 +103,23 @@ fn winrate_all(
             let (p1, p2) = algs.split_at_mut(j);
             let (a1, d1) = &mut p1[i];
             let (a2, d2) = &mut p2[0];
-            let a1_winrate = winrate(a1, *d1, a2, *d2, n, lower, upper);
+            let record = winrate(
+                a1,
+                *d1,
+                a2,
+                *d2,
+                start,
+                n,
+                lower,
+                upper,
+                (names[i], names[j]),
+                games,
+            )?;
 
-            winrates[i][j] = Some(a1_winrate);
-            winrates[j][i] = Some(1. - a1_winrate);
+            records[i][j] = Some(record);
+            records[j][i] = Some(record.reversed());
         }
     }
 
-    winrates
+    Ok(records)
 }
@@ -1,8 +1,11 @@
-use crate::othello::{Board, Disc, Othello};
+use crate::error::ApolloError;
+use crate::othello::{parse_transcript, Board, Disc, GgfGame, GgfMove, Othello, Outcome, Rules};
 use crate::solve::*;
+use chrono::Utc;
 use rand::{thread_rng, Rng};
+use std::{fs::OpenOptions, io::Write, time::Duration};
 
-fn extract_heuristic_name(s: &str) -> Result<Box<dyn Heuristic>, String> {
+fn extract_heuristic_name(s: &str) -> Result<Box<dyn Heuristic>, ApolloError> {
     let heuristic: Box<dyn Heuristic> = match s {
         "0" => HZero::new(),
         "random" => HRandom::new(),
@@ -10,56 +13,299 @@ fn extract_heuristic_name(s: &str) -> Result<Box<dyn Heuristic>, String> {
         "weight" => HWeighted::new(),
         "mobility" => HMobility::new(),
         "weight-mobility" => HWeightedMobility::new(),
-        other => return Err(format!("Unknown heuristic function '{}'!", other)),
+        other => {
+            return Err(ApolloError::Parse(format!(
+                "Unknown heuristic function '{}'!",
+                other
+            )))
+        }
     };
 
     Ok(heuristic)
 }
 
-fn extract_search_name(s: &str, heuristic: Box<dyn Heuristic>) -> Result<Box<dyn Search>, String> {
+/// The size in megabytes of the transposition table for searches
+/// that need one, unless another size is given.
+const DEFAULT_TABLE_MEGABYTES: usize = 16;
+
+/// Options appended to an algorithm string after a '+', such as "+tt64".
+#[derive(Default)]
+struct Modifiers {
+    /// The size in megabytes of a transposition table to search with.
+    table: Option<usize>,
+    /// How far either side of the last value to set the aspiration window.
+    aspiration: Option<Evaluation>,
+    /// How few empty squares must be left to solve the rest of the game exactly.
+    endgame: Option<usize>,
+    /// How few empty squares must be left to solve for a win, draw or loss.
+    wld: Option<usize>,
+}
+
+/// Splits the modifiers off the end of an algorithm string.
+fn extract_modifiers(s: &str) -> Result<(&str, Modifiers), ApolloError> {
+    let mut parts = s.split('+');
+    let base = parts.next().unwrap_or_default();
+    let mut modifiers = Modifiers::default();
+
+    for modifier in parts {
+        if let Some(megabytes) = modifier.strip_prefix("tt") {
+            let megabytes = megabytes.parse::<usize>()?;
+            if megabytes == 0 || megabytes > MAX_TABLE_MEGABYTES {
+                return Err(ApolloError::Invalid(format!(
+                    "Transposition table in '{}' must be between 1 and {} MB!",
+                    s, MAX_TABLE_MEGABYTES
+                )));
+            }
+            modifiers.table = Some(megabytes);
+        } else if let Some(empties) = modifier.strip_prefix("end") {
+            modifiers.endgame = Some(extract_empties(s, empties)?);
+        } else if let Some(empties) = modifier.strip_prefix("wld") {
+            modifiers.wld = Some(extract_empties(s, empties)?);
+        } else if let Some(width) = modifier.strip_prefix("asp") {
+            let width = width.parse::<Evaluation>()?;
+            if width <= 0 {
+                return Err(ApolloError::Invalid(format!(
+                    "Aspiration window in '{}' must be wider than zero!",
+                    s
+                )));
+            }
+            modifiers.aspiration = Some(width);
+        } else {
+            return Err(ApolloError::Parse(format!(
+                "Unknown algorithm modifier '{}'!",
+                modifier
+            )));
+        }
+    }
+
+    Ok((base, modifiers))
+}
+
+/// Parses how few empty squares must be left for an endgame solver to start.
+fn extract_empties(s: &str, empties: &str) -> Result<usize, ApolloError> {
+    let empties = empties.parse::<usize>()?;
+    if empties == 0 {
+        return Err(ApolloError::Invalid(format!(
+            "Endgame solver in '{}' must start with at least 1 empty square!",
+            s
+        )));
+    }
+    Ok(empties)
+}
+
+fn extract_search_name(
+    s: &str,
+    heuristic: Box<dyn Heuristic>,
+    modifiers: &Modifiers,
+) -> Result<Box<dyn Search>, ApolloError> {
+    let table = || modifiers.table.map(TranspositionTable::new);
     let search: Box<dyn Search> = match s {
         "mini" => Minimax::new(heuristic),
-        "ab" => AlphaBeta::new(heuristic),
-        "ab-order" => AlphaBetaOrdering::new(heuristic),
+        "ab" => AlphaBeta::with_options(heuristic, table(), modifiers.aspiration),
+        "ab-order" => AlphaBetaOrdering::with_options(heuristic, table(), modifiers.aspiration),
         "ab-order-unit" => AlphaBetaOrderingUnit::new(heuristic),
-        other => return Err(format!("Unknown search algorithm name '{}'!", other)),
+        "ab-order-history" => AlphaBetaOrderingHistory::new(heuristic),
+        "pvs" => PrincipalVariation::new(heuristic),
+        "mtdf" => {
+            // Every null-window search would start from scratch without a table
+            let table = table().unwrap_or_else(|| TranspositionTable::new(DEFAULT_TABLE_MEGABYTES));
+            Mtdf::new(AlphaBetaOrdering::with_options(
+                heuristic,
+                Some(table),
+                None,
+            ))
+        }
+        other => {
+            return Err(ApolloError::Parse(format!(
+                "Unknown search algorithm name '{}'!",
+                other
+            )))
+        }
     };
 
+    if modifiers.table.is_some() && !matches!(s, "ab" | "ab-order" | "mtdf") {
+        return Err(ApolloError::Invalid(format!(
+            "Search algorithm '{}' can't use a transposition table!",
+            s
+        )));
+    }
+
+    if modifiers.aspiration.is_some() && !matches!(s, "ab" | "ab-order") {
+        return Err(ApolloError::Invalid(format!(
+            "Search algorithm '{}' can't use an aspiration window!",
+            s
+        )));
+    }
+
     Ok(search)
 }
 
-pub fn extract_algorithm_depth(s: &str) -> Result<(Box<dyn Search>, usize), String> {
-    let mut parts = s.split(":");
+pub fn extract_algorithm_depth(s: &str) -> Result<(Box<dyn Search>, usize), ApolloError> {
+    let (base, modifiers) = extract_modifiers(s)?;
+    let mut parts = base.split(":");
     let (search_name, heuristic_name, depth_str) = match (parts.next(), parts.next(), parts.next())
     {
         (Some(search), Some(heuristic), Some(depth)) => (search, heuristic, depth),
-        _ => return Err(format!("Invalid depth-suffixed algorithm string '{}'!", s)),
+        _ => {
+            return Err(ApolloError::Parse(format!(
+                "Invalid depth-suffixed algorithm string '{}'!",
+                s
+            )))
+        }
     };
 
     let heuristic = extract_heuristic_name(heuristic_name)?;
-    let alg = extract_search_name(search_name, heuristic)?;
+    let alg = extract_search_name(search_name, heuristic, &modifiers)?;
+
+    // A time limit in place of the depth searches deeper until it runs out
+    if let Some(limit) = parse_time_limit(depth_str)? {
+        let alg = IterativeDeepening::new(alg, limit);
+        return Ok((with_endgame(alg, &modifiers), usize::MAX));
+    }
 
-    let depth = depth_str.parse::<usize>().map_err(|e| e.to_string())?;
+    let depth = depth_str.parse::<usize>()?;
+    if depth == 0 {
+        return Err(ApolloError::Invalid(format!(
+            "Algorithm '{}' must search at least one move deep!",
+            s
+        )));
+    }
 
-    Ok((alg, depth))
+    Ok((with_endgame(alg, &modifiers), depth))
 }
 
-pub fn extract_search_algorithm(s: &str) -> Result<Box<dyn Search>, String> {
-    let mut parts = s.split(":");
+/// Switches from `alg` to solving for a win, draw or loss, then to solving
+/// the game exactly, once there are few enough empty squares left for each.
+fn with_endgame(alg: Box<dyn Search>, modifiers: &Modifiers) -> Box<dyn Search> {
+    let alg = match modifiers.wld {
+        Some(empties) => Endgame::new(alg, EndgameSolver::wld(), empties),
+        None => alg,
+    };
+
+    match modifiers.endgame {
+        Some(empties) => Endgame::new(alg, EndgameSolver::new(), empties),
+        None => alg,
+    }
+}
+
+/// Parses a time limit in seconds or milliseconds, such as "2s" or "500ms".
+/// Anything without a unit is not a time limit.
+fn parse_time_limit(s: &str) -> Result<Option<Duration>, ApolloError> {
+    let secs = if let Some(ms) = s.strip_suffix("ms") {
+        ms.parse::<f64>()? / 1000.
+    } else if let Some(secs) = s.strip_suffix('s') {
+        secs.parse::<f64>()?
+    } else {
+        return Ok(None);
+    };
+
+    if !secs.is_finite() || secs <= 0. {
+        return Err(ApolloError::Invalid(format!(
+            "Time limit '{}' must be more than zero!",
+            s
+        )));
+    }
+
+    Ok(Some(Duration::from_secs_f64(secs)))
+}
+
+pub fn extract_search_algorithm(s: &str) -> Result<Box<dyn Search>, ApolloError> {
+    let (base, modifiers) = extract_modifiers(s)?;
+    let mut parts = base.split(":");
     let (search_name, heuristic_name) = match (parts.next(), parts.next()) {
         (Some(search), Some(heuristic)) => (search, heuristic),
         _ => {
-            return Err(format!(
+            return Err(ApolloError::Parse(format!(
                 "Invalid non-depth-suffixed algorithm string '{}'!",
                 s
-            ))
+            )))
         }
     };
 
     let heuristic = extract_heuristic_name(heuristic_name)?;
-    let alg = extract_search_name(search_name, heuristic)?;
+    let alg = extract_search_name(search_name, heuristic, &modifiers)?;
 
-    Ok(alg)
+    Ok(with_endgame(alg, &modifiers))
+}
+
+/// The game to start from under the given rules (default standard Othello):
+/// the given one-line position, or the opening on a board of the given size
+/// (default 8). Reversi starts from an empty board instead. Any squares in
+/// `blocked`, such as "a1h8", are then blocked.
+pub fn extract_game(
+    position: Option<&str>,
+    size: Option<&str>,
+    rules: Option<&str>,
+    blocked: Option<&str>,
+) -> Result<Othello, ApolloError> {
+    let game = extract_start(position, size, rules)?;
+    let blocked = match blocked {
+        Some(blocked) => parse_transcript(blocked)?,
+        None => return Ok(game),
+    };
+
+    let mut board = game.board().clone();
+    for pos in blocked {
+        if !board.contains(pos) {
+            return Err(ApolloError::OffBoard(pos));
+        } else if board.at(pos) != Disc::Empty {
+            return Err(ApolloError::Invalid(format!(
+                "Square '{}' can't be blocked, it has a disc!",
+                pos
+            )));
+        }
+        board.block(pos);
+    }
+
+    Ok(Othello::with_rules(board, game.to_move(), game.rules()))
+}
+
+fn extract_start(
+    position: Option<&str>,
+    size: Option<&str>,
+    rules: Option<&str>,
+) -> Result<Othello, ApolloError> {
+    let size = match size {
+        Some(size) => Some(size.parse::<usize>()?),
+        None => None,
+    };
+
+    let rules = match rules {
+        Some(rules) => rules.parse::<Rules>()?,
+        None => Rules::OTHELLO,
+    };
+
+    if let Some(position) = position {
+        let game = position.parse::<Othello>()?;
+        if let Some(size) = size.filter(|size| *size != game.board().width()) {
+            return Err(ApolloError::Invalid(format!(
+                "The position is for a {}x{} board, not {}x{}!",
+                game.board().width(),
+                game.board().height(),
+                size,
+                size
+            )));
+        }
+
+        return Ok(Othello::with_rules(
+            game.board().clone(),
+            game.to_move(),
+            rules,
+        ));
+    }
+
+    let size = size.unwrap_or(8);
+    if !(4..=10).contains(&size) || size % 2 != 0 {
+        return Err(ApolloError::BoardSize(size, size));
+    }
+
+    let board = if rules.fill_center {
+        Board::new(size, size)
+    } else {
+        Board::with_size(size)
+    };
+
+    Ok(Othello::with_rules(board, Disc::Black, rules))
 }
 
 pub fn play(
@@ -68,68 +314,145 @@ pub fn play(
     d1: usize,
     a2: &mut Box<dyn Search>,
     d2: usize,
-) {
-    let p1 = Disc::Black;
-    let p2 = Disc::White;
+) -> Vec<GgfMove> {
+    let mut moves = Vec::new();
 
-    while game.any_valid_moves() {
-        turn(game, a1, d1, p1);
-        turn(game, a2, d2, p2);
+    while !game.is_game_over() {
+        let mv = match game.to_move() {
+            Disc::Black => turn(game, a1, d1),
+            _ => turn(game, a2, d2),
+        };
+        moves.extend(mv);
     }
+
+    moves
+}
+
+fn turn(game: &mut Othello, alg: &mut Box<dyn Search>, depth: usize) -> Option<GgfMove> {
+    let player = game.to_move();
+    let result = alg.search(game, depth);
+    let action = result.best?;
+    game.play(action);
+
+    Some(GgfMove {
+        player,
+        action,
+        eval: Some(result.score as f64),
+        time: Some(result.elapsed.as_secs_f64()),
+    })
 }
 
-fn turn(game: &mut Othello, alg: &mut Box<dyn Search>, depth: usize, player: Disc) {
-    if let (Some(mv), _) = alg.search(game, player, depth) {
-        game.place(mv, player);
+/// A GGF record of `moves` played from `start` between two named players.
+pub fn record_game(
+    start: &Othello,
+    moves: Vec<GgfMove>,
+    black: &str,
+    white: &str,
+) -> Result<GgfGame, ApolloError> {
+    let mut game = GgfGame::new(start, moves)?;
+    game.place = Some(String::from("apollo"));
+    game.date = Some(Utc::now().format("%Y.%m.%d_%H:%M:%S.UTC").to_string());
+    game.black = Some(String::from(black));
+    game.white = Some(String::from(white));
+    Ok(game)
+}
+
+/// Appends `games` to the GGF file at `path`, one game per line.
+pub fn append_ggf(path: &str, games: &[GgfGame]) -> Result<(), ApolloError> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    for game in games {
+        writeln!(file, "{}", game)?;
     }
+
+    Ok(())
 }
 
-pub fn random_game(moves: usize) -> Othello {
-    let game = Othello::new(Board::default());
+pub fn random_game(start: &Othello, moves: usize) -> Othello {
+    let mut game = start.clone();
 
     let mut random = Minimax::new(HRandom::new());
-    let mut player = Disc::Black;
 
     for _ in 0..moves {
-        random.search(&game, player, 1);
-        player = player.opponent();
+        turn(&mut game, &mut random, 1);
     }
 
     game
 }
 
+/// The games played between two algorithms, from the first one's point of view.
+#[derive(Copy, Clone, Default)]
+pub struct Record {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    /// The sum of the final disc differentials over every game.
+    pub differential: isize,
+}
+
+impl Record {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn average_differential(&self) -> f64 {
+        self.differential as f64 / self.games().max(1) as f64
+    }
+
+    /// The same games from the second algorithm's point of view.
+    pub fn reversed(&self) -> Record {
+        Record {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+            differential: -self.differential,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn winrate(
     a1: &mut Box<dyn Search>,
     d1: usize,
     a2: &mut Box<dyn Search>,
     d2: usize,
+    start: &Othello,
     n: usize,
     lower: usize,
     upper: usize,
-) -> f64 {
+    (name1, name2): (&str, &str),
+    games: &mut Vec<GgfGame>,
+) -> Result<Record, ApolloError> {
     let mut rng = thread_rng();
 
-    let mut a1wins = 0;
+    let mut record = Record::default();
     let mut a1_black = true;
 
     for _ in 0..n {
         let num_moves = lower + rng.gen::<usize>() % (upper - lower);
-        let mut game = random_game(num_moves);
+        let opening = random_game(start, num_moves);
+        let mut game = opening.clone();
 
         if a1_black {
-            play(&mut game, a1, d1, a2, d2);
+            let moves = play(&mut game, a1, d1, a2, d2);
+            games.push(record_game(&opening, moves, name1, name2)?);
         } else {
-            play(&mut game, a2, d2, a1, d1);
+            let moves = play(&mut game, a2, d2, a1, d1);
+            games.push(record_game(&opening, moves, name2, name1)?);
         }
 
-        let black_won = game.winner().unwrap_or(Disc::White) == Disc::Black;
+        let a1_player = if a1_black { Disc::Black } else { Disc::White };
+        let result = game.result();
 
-        if (black_won && a1_black) || (!black_won && !a1_black) {
-            a1wins += 1;
+        match result.outcome() {
+            Outcome::Win(player) if player == a1_player => record.wins += 1,
+            Outcome::Win(_) => record.losses += 1,
+            Outcome::Draw => record.draws += 1,
         }
+        record.differential += result.margin(a1_player);
 
         a1_black = !a1_black;
     }
 
-    a1wins as f64 / n as f64
+    Ok(record)
 }
@@ -0,0 +1,64 @@
+use crate::othello::Position;
+use std::{
+    fmt::{Debug, Display},
+    io,
+    num::{ParseFloatError, ParseIntError},
+};
+
+/// Everything that can go wrong in apollo, from bad input to failed I/O.
+pub enum ApolloError {
+    /// Text that could not be parsed, such as a square, position,
+    /// game record or algorithm string.
+    Parse(String),
+    /// A move that is not legal in the position it was played in.
+    IllegalMove(String),
+    /// A square that is not on the board.
+    OffBoard(Position),
+    /// A board too large to be represented, or without a center to start from.
+    BoardSize(usize, usize),
+    /// Input that was understood, but can't be used.
+    Invalid(String),
+    Io(io::Error),
+}
+
+impl Display for ApolloError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApolloError::Parse(message)
+            | ApolloError::IllegalMove(message)
+            | ApolloError::Invalid(message) => write!(f, "{}", message),
+            ApolloError::OffBoard(pos) => write!(f, "Square '{}' is not on the board!", pos),
+            ApolloError::BoardSize(width, height) => {
+                write!(f, "A {}x{} board is not supported!", width, height)
+            }
+            ApolloError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// Errors returned from `main` are printed with `Debug`, so it reads the same as `Display`
+impl Debug for ApolloError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for ApolloError {}
+
+impl From<io::Error> for ApolloError {
+    fn from(e: io::Error) -> Self {
+        ApolloError::Io(e)
+    }
+}
+
+impl From<ParseIntError> for ApolloError {
+    fn from(e: ParseIntError) -> Self {
+        ApolloError::Parse(e.to_string())
+    }
+}
+
+impl From<ParseFloatError> for ApolloError {
+    fn from(e: ParseFloatError) -> Self {
+        ApolloError::Parse(e.to_string())
+    }
+}
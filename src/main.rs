@@ -3,12 +3,14 @@ extern crate clap;
 
 mod benchmark;
 mod common;
+pub mod error;
 pub mod othello;
+mod perft;
 mod play;
 mod sim;
 pub mod solve;
 
-fn main() -> Result<(), String> {
+fn main() -> Result<(), error::ApolloError> {
     let matches = clap_app!(Apollo =>
         (version: "v0.1.0")
         (author: "Ryan Bergman <rybergy@gmail.com>")
@@ -30,6 +32,11 @@ fn main() -> Result<(), String> {
                 (@arg number: -n --number +takes_value "The number of games to play (default 100).")
                 (@arg lower: -l --lower +takes_value "The lower bound of random moves to perform (default 5)")
                 (@arg upper: -u --upper +takes_value "The upper bound of random moves to perform (default 40)")
+                (@arg position: -p --position +takes_value +allow_hyphen_values "The position to start from, as one X/O/- per square followed by the side to move (default is the standard opening).")
+                (@arg size: -s --size +takes_value "The size of the board, which must be even (default 8).")
+                (@arg rules: -r --rules +takes_value "The rules to play by: othello, misere or reversi, which can be combined with + (default othello).")
+                (@arg blocked: -b --blocked +takes_value "Squares to block so they never hold a disc, e.g. a1h8.")
+                (@arg ggf: -g --ggf +takes_value "Appends every game played to the given GGF file.")
                 (@arg algorithms: ... +required "The algorithms to use (depth-suffixed).")
             )
             (@subcommand performance =>
@@ -42,6 +49,10 @@ fn main() -> Result<(), String> {
                 (@arg depth: -d --depth +takes_value "The maximum depth limit (default 7).")
                 (@arg lower: -l --lower +takes_value "The lower bound of random moves to perform (default 5)")
                 (@arg upper: -u --upper +takes_value "The upper bound of random moves to perform (default 40)")
+                (@arg position: -p --position +takes_value +allow_hyphen_values "The position to start from, as one X/O/- per square followed by the side to move (default is the standard opening).")
+                (@arg size: -s --size +takes_value "The size of the board, which must be even (default 8).")
+                (@arg rules: -r --rules +takes_value "The rules to play by: othello, misere or reversi, which can be combined with + (default othello).")
+                (@arg blocked: -b --blocked +takes_value "Squares to block so they never hold a disc, e.g. a1h8.")
                 (@arg algorithms: ... +required "All algorithms to benchmark (not depth-suffixed).")
             )
         )
@@ -53,12 +64,35 @@ fn main() -> Result<(), String> {
             (@setting ColoredHelp)
             (@arg algorithm1: +required +takes_value {is_algorithm_string} "The algorithm to use for player 1.")
             (@arg algorithm2: +required +takes_value {is_algorithm_string} "The algorithm to use for player 2.")
+            (@arg ggf: -g --ggf +takes_value "Appends the game to the given GGF file.")
+            (@arg position: -p --position +takes_value +allow_hyphen_values "The position to start from, as one X/O/- per square followed by the side to move (default is the standard opening).")
+            (@arg size: -s --size +takes_value "The size of the board, which must be even (default 8).")
+            (@arg rules: -r --rules +takes_value "The rules to play by: othello, misere or reversi, which can be combined with + (default othello).")
+            (@arg blocked: -b --blocked +takes_value "Squares to block so they never hold a disc, e.g. a1h8.")
         )
         (@subcommand play =>
-            (about: "Play a game of othello against the CPU. Use W/A/S/D to move the selection and press space to drop a piece.")
+            (about: "Play a game of othello against the CPU. Use W/A/S/D to move the selection, press space to drop a piece and U to undo.")
             (version: "v0.1.0")
             (author: "Ryan Bergman <rybergy@gmail.com>")
             (@setting ColoredHelp)
+            (@arg algorithm: -a --algorithm +takes_value {is_algorithm_string} "The algorithm for the CPU to use (default ab-order:weight-mobility:4).")
+            (@arg position: -p --position +takes_value +allow_hyphen_values "The position to start from, as one X/O/- per square followed by the side to move (default is the standard opening).")
+            (@arg size: -s --size +takes_value "The size of the board, which must be even (default 8).")
+            (@arg rules: -r --rules +takes_value "The rules to play by: othello, misere or reversi, which can be combined with + (default othello).")
+            (@arg blocked: -b --blocked +takes_value "Squares to block so they never hold a disc, e.g. a1h8.")
+        )
+        (@subcommand perft =>
+            (about: "Counts the leaf nodes of the game tree to verify and time move generation.")
+            (version: "v0.1.0")
+            (author: "Ryan Bergman <rybergy@gmail.com>")
+            (@setting ArgRequiredElseHelp)
+            (@setting ColoredHelp)
+            (@arg depth: +required +takes_value "The depth to count leaf nodes to.")
+            (@arg divide: --divide "Counts the leaf nodes under each move from the position instead.")
+            (@arg position: -p --position +takes_value +allow_hyphen_values "The position to start from, as one X/O/- per square followed by the side to move (default is the standard opening).")
+            (@arg size: -s --size +takes_value "The size of the board, which must be even (default 8).")
+            (@arg rules: -r --rules +takes_value "The rules to play by: othello, misere or reversi, which can be combined with + (default othello).")
+            (@arg blocked: -b --blocked +takes_value "Squares to block so they never hold a disc, e.g. a1h8.")
         )
     )
     .get_matches();
@@ -68,13 +102,15 @@ fn main() -> Result<(), String> {
     } else if let Some(m) = matches.subcommand_matches("sim") {
         sim::main(m)?;
     } else if let Some(m) = matches.subcommand_matches("play") {
-        play::main(m).map_err(|e| e.to_string())?;
+        play::main(m)?;
+    } else if let Some(m) = matches.subcommand_matches("perft") {
+        perft::main(m)?;
     }
 
     Ok(())
 }
 
 fn is_algorithm_string(s: String) -> Result<(), String> {
-    common::extract_algorithm_depth(&*s)?;
+    common::extract_algorithm_depth(&s).map_err(|e| e.to_string())?;
     Ok(())
 }
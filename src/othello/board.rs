@@ -1,4 +1,9 @@
-use std::fmt::Display;
+use crate::error::ApolloError;
+use std::{
+    fmt::Display,
+    ops::{BitAnd, BitOr, BitOrAssign, Shl, Shr},
+    str::FromStr,
+};
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum Disc {
@@ -8,6 +13,7 @@ pub enum Disc {
 }
 
 impl Disc {
+    /// The other player. Panics if given `Disc::Empty`; see `try_opponent`.
     pub fn opponent(&self) -> Disc {
         match self {
             Disc::Black => Disc::White,
@@ -15,6 +21,39 @@ impl Disc {
             Disc::Empty => panic!("attempting to take opposite of empty disc!"),
         }
     }
+
+    pub fn try_opponent(&self) -> Result<Disc, ApolloError> {
+        match self {
+            Disc::Empty => Err(ApolloError::Invalid(String::from(
+                "An empty square has no opponent!",
+            ))),
+            player => Ok(player.opponent()),
+        }
+    }
+}
+
+impl Disc {
+    /// The disc for a square in a position string: `X` for black,
+    /// `O` for white, and `-` for empty.
+    pub(crate) fn from_char(c: char) -> Result<Disc, ApolloError> {
+        match c {
+            'X' | 'x' | '*' | 'B' | 'b' => Ok(Disc::Black),
+            'O' | 'o' | 'W' | 'w' => Ok(Disc::White),
+            '-' | '.' | '_' => Ok(Disc::Empty),
+            other => Err(ApolloError::Parse(format!(
+                "Invalid square '{}' in position!",
+                other
+            ))),
+        }
+    }
+
+    pub(crate) fn to_char(self) -> char {
+        match self {
+            Disc::Black => 'X',
+            Disc::White => 'O',
+            Disc::Empty => '-',
+        }
+    }
 }
 
 impl Display for Disc {
@@ -30,6 +69,7 @@ impl Display for Disc {
         write!(f, "{}", c)
     }
 }
+
 #[derive(Hash, Copy, Clone, PartialEq, Eq)]
 pub struct Position {
     pub row: isize,
@@ -42,29 +82,257 @@ impl Position {
     }
 }
 
+/// Displays the position in standard Othello notation: a column letter
+/// followed by a row number, where "a1" is the top-left corner.
+/// Positions with no such name are displayed as `(row, col)`.
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.row >= 0 && (0..26).contains(&self.col) {
+            write!(f, "{}{}", (b'a' + self.col as u8) as char, self.row + 1)
+        } else {
+            write!(f, "({}, {})", self.row, self.col)
+        }
+    }
+}
+
+impl FromStr for Position {
+    type Err = ApolloError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let letter = chars.next().filter(|c| c.is_ascii_alphabetic());
+        let digits = chars.as_str();
+
+        let number = digits
+            .parse::<isize>()
+            .ok()
+            .filter(|n| *n >= 1 && digits.chars().all(|c| c.is_ascii_digit()));
+
+        match (letter, number) {
+            (Some(letter), Some(number)) => {
+                let col = letter.to_ascii_lowercase() as isize - 'a' as isize;
+                Ok(Position::new(number - 1, col))
+            }
+            _ => Err(ApolloError::Parse(format!("Invalid square '{}'!", s))),
+        }
+    }
+}
+
+/// A set of squares, one bit per square in row-major order.
+/// It is wide enough for boards up to 11x11, such as 10x10 Grand Othello.
+pub(crate) type Bits = u128;
+
+/// A bitboard that moves can be generated on. Boards of up to 64 squares,
+/// such as the standard 8x8 board, generate moves on a `u64`, which is
+/// much faster to shift than `Bits`.
+pub(crate) trait Bitboard:
+    Copy
+    + PartialEq
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitOrAssign
+    + Shl<isize, Output = Self>
+    + Shr<isize, Output = Self>
+{
+    const ZERO: Self;
+
+    /// The squares of `bits` that fit in this bitboard.
+    fn narrow(bits: Bits) -> Self;
+
+    fn widen(self) -> Bits;
+}
+
+impl Bitboard for u64 {
+    const ZERO: u64 = 0;
+
+    fn narrow(bits: Bits) -> u64 {
+        bits as u64
+    }
+
+    fn widen(self) -> Bits {
+        self as Bits
+    }
+}
+
+impl Bitboard for Bits {
+    const ZERO: Bits = 0;
+
+    fn narrow(bits: Bits) -> Bits {
+        bits
+    }
+
+    fn widen(self) -> Bits {
+        self
+    }
+}
+
+/// What it takes to shift bitboards of type `B` around one board.
+pub(crate) struct Shifts<B> {
+    width: usize,
+    full: B,
+    not_first_col: B,
+    not_last_col: B,
+}
+
+impl<B: Bitboard> Shifts<B> {
+    /// Moves every square in `bits` one step in the direction `(dr, dc)`,
+    /// dropping the squares that would fall off the board.
+    pub(crate) fn shift(&self, bits: B, (dr, dc): (isize, isize)) -> B {
+        let bits = match dc {
+            1 => bits & self.not_last_col,
+            -1 => bits & self.not_first_col,
+            _ => bits,
+        };
+
+        let amount = dr * self.width as isize + dc;
+        let shifted = if amount > 0 {
+            bits << amount
+        } else {
+            bits >> -amount
+        };
+
+        shifted & self.full
+    }
+}
+
+/// A set of squares on a board, such as the discs flipped by a move.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DiscSet {
+    bits: Bits,
+    width: usize,
+}
+
+impl DiscSet {
+    pub(crate) fn new(bits: Bits, width: usize) -> DiscSet {
+        DiscSet { bits, width }
+    }
+
+    pub(crate) fn bits(&self) -> Bits {
+        self.bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        // Any row below this one would be past the last bit
+        let rows = (Bits::BITS as usize / self.width) as isize;
+        (0..rows).contains(&pos.row)
+            && (0..self.width as isize).contains(&pos.col)
+            && self.bits & (1 << (pos.row * self.width as isize + pos.col)) != 0
+    }
+
+    /// Iterates over the squares in the set in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = Position> {
+        let (mut bits, width) = (self.bits, self.width);
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                None
+            } else {
+                let index = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(Position::new(
+                    (index / width) as isize,
+                    (index % width) as isize,
+                ))
+            }
+        })
+    }
+}
+
 /// The Othello board.
 ///
-/// It is represented by a 1-dimensional vector,
-/// where there are empty spaces at every edge
-/// of the board.
-#[derive(Clone)]
+/// It is represented as a pair of bitboards, one for each
+/// player, where square `(row, col)` is bit `row * width + col`.
+/// Squares can also be blocked, so that they never hold a disc.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Board {
     width: usize,
     height: usize,
-    tiles: Vec<Disc>,
+    black: Bits,
+    white: Bits,
+    blocked: Bits,
+    // Every square on the board
+    full: Bits,
+    // Every square not in the first / last column, used to stop
+    // horizontal shifts from wrapping onto the neighbouring row
+    not_first_col: Bits,
+    not_last_col: Bits,
 }
 
 impl Board {
+    /// An empty board. Panics if the board is too large; see `try_new`.
     pub fn new(width: usize, height: usize) -> Board {
-        Board {
+        match Board::try_new(width, height) {
+            Ok(board) => board,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// An empty board, unless it has no squares or more than fit in a bitboard.
+    pub fn try_new(width: usize, height: usize) -> Result<Board, ApolloError> {
+        let fits = width
+            .checked_mul(height)
+            .is_some_and(|squares| squares <= Bits::BITS as usize);
+        if width == 0 || height == 0 || !fits {
+            return Err(ApolloError::BoardSize(width, height));
+        }
+
+        let mut full = 0;
+        let mut first_col = 0;
+        let mut last_col = 0;
+        for row in 0..height {
+            first_col |= 1 << (row * width);
+            last_col |= 1 << (row * width + width - 1);
+            for col in 0..width {
+                full |= 1 << (row * width + col);
+            }
+        }
+
+        Ok(Board {
             width,
             height,
-            tiles: (0..((width + 2) * (height + 2)))
-                .map(|_| Disc::Empty)
-                .collect(),
+            black: 0,
+            white: 0,
+            blocked: 0,
+            full,
+            not_first_col: full & !first_col,
+            not_last_col: full & !last_col,
+        })
+    }
+
+    /// A square board with the four starting discs in the center.
+    /// Panics if the size is not even and at least 4; see `try_with_size`.
+    pub fn with_size(size: usize) -> Board {
+        match Board::try_with_size(size) {
+            Ok(board) => board,
+            Err(e) => panic!("{}", e),
         }
     }
 
+    /// A square board with the four starting discs in the center,
+    /// unless the size is odd, less than 4 or too large.
+    pub fn try_with_size(size: usize) -> Result<Board, ApolloError> {
+        if size < 4 || size % 2 != 0 {
+            return Err(ApolloError::BoardSize(size, size));
+        }
+
+        let mut board = Board::try_new(size, size)?;
+        let mid = (size / 2) as isize;
+        board
+            .white(Position::new(mid - 1, mid - 1))
+            .black(Position::new(mid - 1, mid))
+            .black(Position::new(mid, mid - 1))
+            .white(Position::new(mid, mid));
+
+        Ok(board)
+    }
+
     pub fn black(&mut self, pos: Position) -> &mut Self {
         self.set(pos, Disc::Black);
         self
@@ -75,33 +343,181 @@ impl Board {
         self
     }
 
+    /// Permanently blocks `pos`, removing any disc on it. A blocked square can
+    /// never be played on, and stops any line of discs running through it.
+    pub fn block(&mut self, pos: Position) -> &mut Self {
+        self.set(pos, Disc::Empty);
+        self.blocked |= 1 << self.index(pos);
+        self
+    }
+
+    pub fn try_block(&mut self, pos: Position) -> Result<&mut Self, ApolloError> {
+        self.try_index(pos)?;
+        Ok(self.block(pos))
+    }
+
+    /// Whether `pos` is a blocked square on this board.
+    pub fn is_blocked(&self, pos: Position) -> bool {
+        self.contains(pos) && self.blocked & (1 << self.index(pos)) != 0
+    }
+
+    /// Every blocked square.
+    pub fn blocked(&self) -> DiscSet {
+        DiscSet::new(self.blocked, self.width)
+    }
+
+    /// Puts `tile` on `pos`, unblocking it if it was blocked.
+    /// Panics if `pos` is not on the board; see `try_set`.
     pub fn set(&mut self, pos: Position, tile: Disc) {
-        let index = self.pos(pos);
-        let change = self.tiles.get_mut(index).expect(&*format!(
-            "index {} out of board range 0..{}!",
-            index,
-            self.width * self.height
-        ));
-        *change = tile;
+        let bit = 1 << self.index(pos);
+        self.black &= !bit;
+        self.white &= !bit;
+        self.blocked &= !bit;
+        match tile {
+            Disc::Black => self.black |= bit,
+            Disc::White => self.white |= bit,
+            Disc::Empty => (),
+        }
     }
 
+    pub fn try_set(&mut self, pos: Position, tile: Disc) -> Result<(), ApolloError> {
+        self.try_index(pos)?;
+        self.set(pos, tile);
+        Ok(())
+    }
+
+    /// The disc on `pos`. Panics if `pos` is not on the board; see `try_at`.
     pub fn at(&self, pos: Position) -> Disc {
-        let index = self.pos(pos);
-        *self.tiles.get(index).expect(&*format!(
-            "index {} out of board range 0..{}!",
-            index,
-            self.width * self.height
-        ))
+        let bit = 1 << self.index(pos);
+        if self.black & bit != 0 {
+            Disc::Black
+        } else if self.white & bit != 0 {
+            Disc::White
+        } else {
+            Disc::Empty
+        }
     }
 
-    fn pos(&self, pos: Position) -> usize {
+    pub fn try_at(&self, pos: Position) -> Result<Disc, ApolloError> {
+        self.try_index(pos)?;
+        Ok(self.at(pos))
+    }
+
+    /// Whether `pos` is a square on this board.
+    pub fn contains(&self, pos: Position) -> bool {
+        pos.row >= 0
+            && pos.col >= 0
+            && pos.row < self.height as isize
+            && pos.col < self.width as isize
+    }
+
+    pub(crate) fn index(&self, pos: Position) -> usize {
+        if !self.contains(pos) {
+            panic!("{} is off the {}x{} board!", pos, self.width, self.height);
+        }
         (pos.row * self.width as isize + pos.col) as usize
     }
 
+    pub(crate) fn try_index(&self, pos: Position) -> Result<usize, ApolloError> {
+        if self.contains(pos) {
+            Ok(self.index(pos))
+        } else {
+            Err(ApolloError::OffBoard(pos))
+        }
+    }
+
+    pub(crate) fn position(&self, index: usize) -> Position {
+        Position::new((index / self.width) as isize, (index % self.width) as isize)
+    }
+
+    /// Iterates over the positions of every square in `bits`.
+    pub(crate) fn positions(&self, mut bits: Bits) -> impl Iterator<Item = Position> + '_ {
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                None
+            } else {
+                let index = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(self.position(index))
+            }
+        })
+    }
+
+    /// The squares occupied by `player`'s discs.
+    pub(crate) fn discs(&self, player: Disc) -> Bits {
+        match player {
+            Disc::Black => self.black,
+            Disc::White => self.white,
+            Disc::Empty => self.empty(),
+        }
+    }
+
+    pub(crate) fn empty(&self) -> Bits {
+        self.full & !(self.black | self.white | self.blocked)
+    }
+
+    /// The four squares in the middle of the board, or none
+    /// on a board less than two squares wide or high.
+    pub(crate) fn center(&self) -> Bits {
+        if self.width < 2 || self.height < 2 {
+            return 0;
+        }
+
+        let corner: Bits = 1 << ((self.height / 2 - 1) * self.width + self.width / 2 - 1);
+        corner | corner << 1 | corner << self.width | corner << (self.width + 1)
+    }
+
+    /// Flips every disc in `bits` over to `player`.
+    pub(crate) fn flip(&mut self, bits: Bits, player: Disc) {
+        match player {
+            Disc::Black => {
+                self.black |= bits;
+                self.white &= !bits;
+            }
+            Disc::White => {
+                self.white |= bits;
+                self.black &= !bits;
+            }
+            Disc::Empty => panic!("attempting to flip discs to empty!"),
+        }
+    }
+
+    /// Whether every square fits in a `u64`, so that moves
+    /// can be generated on the narrower bitboard.
+    pub(crate) fn is_narrow(&self) -> bool {
+        self.len() <= u64::BITS as usize
+    }
+
+    /// The masks for shifting bitboards of type `B`, which must
+    /// be wide enough for every square, around this board.
+    pub(crate) fn shifts<B: Bitboard>(&self) -> Shifts<B> {
+        Shifts {
+            width: self.width,
+            full: B::narrow(self.full),
+            not_first_col: B::narrow(self.not_first_col),
+            not_last_col: B::narrow(self.not_last_col),
+        }
+    }
+
+    /// The board as one `X`, `O`, `-` or `#` (blocked) per square in row-major
+    /// order, from a1 to h8, which can be parsed back into a board.
+    pub fn to_position_string(&self) -> String {
+        (0..self.len())
+            .map(|index| match self.position(index) {
+                pos if self.is_blocked(pos) => '#',
+                pos => self.at(pos).to_char(),
+            })
+            .collect()
+    }
+
     pub fn len(&self) -> usize {
         self.width * self.height
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -113,17 +529,41 @@ impl Board {
 
 impl Default for Board {
     fn default() -> Self {
-        let mut board = Board::new(8, 8);
-        // Middle tiles: (3, 3), (3, 4), (4, 3), (3, 3)
+        // Middle tiles: (3, 3), (3, 4), (4, 3), (4, 4)
         //     as index:     27,     28,     35,     36
-        //        tiles:  black,  white,  white,  black
-        board
-            .black(Position { row: 3, col: 3 })
-            .white(Position { row: 3, col: 4 })
-            .white(Position { row: 4, col: 3 })
-            .black(Position { row: 4, col: 4 });
+        //       square:     d4,     e4,     d5,     e5
+        //        tiles:  white,  black,  black,  white
+        Board::with_size(8)
+    }
+}
 
-        board
+/// Parses a square board from one `X`, `O`, `-` or `#` per square in
+/// row-major order, as written by `Board::to_position_string`.
+impl FromStr for Board {
+    type Err = ApolloError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let squares = s.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+
+        let size = (squares.len() as f64).sqrt() as usize;
+        if size == 0 || size * size != squares.len() {
+            return Err(ApolloError::Parse(format!(
+                "A position must have a square number of squares, but found {}!",
+                squares.len()
+            )));
+        }
+
+        let mut board = Board::try_new(size, size)?;
+        for (index, c) in squares.into_iter().enumerate() {
+            let pos = board.position(index);
+            if c == '#' {
+                board.block(pos);
+            } else {
+                board.set(pos, Disc::from_char(c)?);
+            }
+        }
+
+        Ok(board)
     }
 }
 
@@ -157,13 +597,17 @@ impl Display for Board {
         for row in 0..self.height {
             write!(f, "│")?;
             for col in 0..self.width {
-                let tile = self.at(Position {
+                let pos = Position {
                     row: row as isize,
                     col: col as isize,
-                });
-                write!(f, "{}│", tile)?;
+                };
+                if self.is_blocked(pos) {
+                    write!(f, "╳╳╳│")?;
+                } else {
+                    write!(f, "{}│", self.at(pos))?;
+                }
             }
-            writeln!(f, "")?;
+            writeln!(f)?;
             if row < self.height - 1 {
                 display_board_row(f, self.width)?;
             }
@@ -172,3 +616,51 @@ impl Display for Board {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_round_trip_through_notation() {
+        for row in 0..10 {
+            for col in 0..10 {
+                let pos = Position::new(row, col);
+                assert!(pos.to_string().parse::<Position>().ok() == Some(pos));
+            }
+        }
+        assert!("H8".parse::<Position>().ok() == Some(Position::new(7, 7)));
+
+        for bad in &["", "a", "1a", "aa1", "a0", "a-1", "a+1", "é1"] {
+            assert!(bad.parse::<Position>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn far_positions_are_off_the_board() {
+        let far = "a9223372036854775807".parse::<Position>().unwrap();
+        let board = Board::default();
+        assert!(!board.contains(far));
+        assert!(board.try_at(far).is_err());
+
+        let set = DiscSet::new(!0, board.width());
+        assert!(!set.contains(far));
+        assert!(!set.contains(Position::new(0, isize::MAX)));
+        assert!(set.contains(Position::new(15, 7)));
+        assert!(!set.contains(Position::new(16, 0)));
+    }
+
+    #[test]
+    fn oversized_boards_are_rejected() {
+        assert!(Board::try_new(usize::MAX, 2).is_err());
+        assert!(Board::try_new(12, 12).is_err());
+        assert!(Board::try_with_size(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn narrow_boards_have_no_center() {
+        assert_eq!(Board::new(1, 1).center(), 0);
+        assert_eq!(Board::new(4, 1).center(), 0);
+        assert_eq!(Board::new(2, 2).center(), 0b1111);
+    }
+}
@@ -0,0 +1,361 @@
+use super::{Action, Board, Disc, Othello, Position, Rules};
+use crate::error::ApolloError;
+use std::{fmt::Display, str::FromStr};
+
+/// A move in a GGF game record, along with the evaluation
+/// and the time in seconds the player reported for it.
+#[derive(Clone)]
+pub struct GgfMove {
+    pub player: Disc,
+    pub action: Action,
+    pub eval: Option<f64>,
+    pub time: Option<f64>,
+}
+
+/// A game record in the Generic Game Format, as used by
+/// Othello servers and most other Othello software.
+#[derive(Clone)]
+pub struct GgfGame {
+    pub place: Option<String>,
+    pub date: Option<String>,
+    pub black: Option<String>,
+    pub white: Option<String>,
+    /// The final disc differential from black's point of view, with empty
+    /// squares awarded to the winner. It is positive when black won.
+    pub result: Option<f64>,
+    pub start: Othello,
+    pub moves: Vec<GgfMove>,
+}
+
+impl GgfGame {
+    /// A record of `moves` played from `start`, with the result
+    /// filled in if they take the game to its end. GGF has no notation
+    /// for Reversi rules, so games played under them can't be recorded.
+    pub fn new(start: &Othello, moves: Vec<GgfMove>) -> Result<GgfGame, ApolloError> {
+        if start.rules().fill_center {
+            return Err(ApolloError::Invalid(String::from(
+                "Reversi games can't be recorded as GGF!",
+            )));
+        }
+
+        let mut game = GgfGame {
+            place: None,
+            date: None,
+            black: None,
+            white: None,
+            result: None,
+            start: Othello::with_rules(start.board().clone(), start.to_move(), start.rules()),
+            moves,
+        };
+
+        let end = game.replay()?;
+        if end.is_game_over() {
+            game.result = Some(end.result().margin(Disc::Black) as f64);
+        }
+
+        Ok(game)
+    }
+
+    /// A record of every action in `game`'s history, if it has been kept.
+    pub fn from_othello(game: &Othello) -> Option<Result<GgfGame, ApolloError>> {
+        let start = game.replay(0)?;
+        let mut player = start.to_move();
+
+        let moves = game
+            .history()?
+            .iter()
+            .map(|action| {
+                let mv = GgfMove {
+                    player,
+                    action: *action,
+                    eval: None,
+                    time: None,
+                };
+                player = player.opponent();
+                mv
+            })
+            .collect();
+
+        Some(GgfGame::new(&start, moves))
+    }
+
+    /// Plays every move from the starting position, keeping the history.
+    pub fn replay(&self) -> Result<Othello, ApolloError> {
+        let mut game = self.start.clone();
+        game.record_history();
+
+        for mv in self.moves.iter() {
+            // Some records leave out forced passes
+            if mv.player != game.to_move() && game.is_legal(Action::Pass) {
+                game.pass();
+            }
+
+            if mv.player != game.to_move() || !game.is_legal(mv.action) {
+                return Err(ApolloError::IllegalMove(format!(
+                    "Illegal move '{}' in GGF game!",
+                    action_name(mv.action)
+                )));
+            }
+
+            game.play(mv.action);
+        }
+
+        Ok(game)
+    }
+
+    /// Parses every game in a GGF file, each enclosed in `(;` and `;)`.
+    pub fn parse_all(s: &str) -> Result<Vec<GgfGame>, ApolloError> {
+        let mut games = Vec::new();
+        let mut rest = s;
+
+        while let Some(start) = rest.find("(;") {
+            let end = rest[start..]
+                .find(";)")
+                .ok_or_else(|| ApolloError::Parse(String::from("Unterminated GGF game!")))?;
+            games.push(rest[start..start + end + 2].parse()?);
+            rest = &rest[start + end + 2..];
+        }
+
+        Ok(games)
+    }
+}
+
+impl Display for GgfGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let board = self.start.board();
+
+        write!(f, "(;GM[Othello]")?;
+        if let Some(place) = &self.place {
+            write!(f, "PC[{}]", place)?;
+        }
+        if let Some(date) = &self.date {
+            write!(f, "DT[{}]", date)?;
+        }
+        if let Some(black) = &self.black {
+            write!(f, "PB[{}]", black)?;
+        }
+        if let Some(white) = &self.white {
+            write!(f, "PW[{}]", white)?;
+        }
+        // Anti-Othello is marked by an `a` after the board size
+        let anti = if self.start.rules().misere { "a" } else { "" };
+        write!(f, "TY[{}{}]", board.width(), anti)?;
+        if let Some(result) = self.result {
+            write!(f, "RE[{:+.3}]", result)?;
+        }
+
+        write!(f, "BO[{}", board.width())?;
+        for row in 0..board.height() {
+            write!(f, " ")?;
+            for col in 0..board.width() {
+                // Blocked squares have no GGF notation, so they are written as in positions
+                let pos = Position::new(row as isize, col as isize);
+                if board.is_blocked(pos) {
+                    write!(f, "#")?;
+                } else {
+                    write!(f, "{}", ggf_char(board.at(pos)))?;
+                }
+            }
+        }
+        write!(f, " {}]", ggf_char(self.start.to_move()))?;
+
+        for mv in self.moves.iter() {
+            let tag = match mv.player {
+                Disc::White => "W",
+                _ => "B",
+            };
+            write!(f, "{}[{}", tag, action_name(mv.action))?;
+            match (mv.eval, mv.time) {
+                (Some(eval), Some(time)) => write!(f, "/{:.2}/{:.2}", eval, time)?,
+                (Some(eval), None) => write!(f, "/{:.2}", eval)?,
+                (None, Some(time)) => write!(f, "//{:.2}", time)?,
+                (None, None) => (),
+            }
+            write!(f, "]")?;
+        }
+
+        write!(f, ";)")
+    }
+}
+
+impl FromStr for GgfGame {
+    type Err = ApolloError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let body = s
+            .trim()
+            .strip_prefix("(;")
+            .and_then(|s| s.strip_suffix(";)"))
+            .ok_or_else(|| {
+                ApolloError::Parse(String::from(
+                    "A GGF game must be enclosed in '(;' and ';)'!",
+                ))
+            })?;
+
+        let mut game = GgfGame {
+            place: None,
+            date: None,
+            black: None,
+            white: None,
+            result: None,
+            start: Othello::new(Board::default()),
+            moves: Vec::new(),
+        };
+
+        let mut rules = Rules::OTHELLO;
+        let mut rest = body;
+        while let Some(open) = rest.find('[') {
+            let close = rest[open..]
+                .find(']')
+                .ok_or_else(|| ApolloError::Parse(String::from("Unterminated GGF property!")))?;
+            let name = rest[..open].trim();
+            let value = &rest[open + 1..open + close];
+            rest = &rest[open + close + 1..];
+
+            match name {
+                "GM" if !value.eq_ignore_ascii_case("othello") => {
+                    return Err(ApolloError::Parse(format!(
+                        "Unsupported GGF game type '{}'!",
+                        value
+                    )))
+                }
+                "PC" => game.place = Some(value.to_string()),
+                "DT" => game.date = Some(value.to_string()),
+                "PB" => game.black = Some(value.to_string()),
+                "PW" => game.white = Some(value.to_string()),
+                "TY" => rules.misere = value.contains('a'),
+                // Results may be suffixed with how the game ended, e.g. ":r" for resignation
+                "RE" => game.result = value.split(':').next().and_then(|r| r.parse().ok()),
+                "BO" => game.start = parse_board(value)?,
+                "B" | "W" => game.moves.push(parse_move(name, value)?),
+                _ => (),
+            }
+        }
+
+        let start = game.start;
+        game.start = Othello::with_rules(start.board().clone(), start.to_move(), rules);
+
+        Ok(game)
+    }
+}
+
+fn ggf_char(disc: Disc) -> char {
+    match disc {
+        Disc::Black => '*',
+        Disc::White => 'O',
+        Disc::Empty => '-',
+    }
+}
+
+fn action_name(action: Action) -> String {
+    match action {
+        Action::Place(pos) => pos.to_string(),
+        Action::Pass => String::from("PA"),
+    }
+}
+
+/// Parses the board size, every square and the side to move, e.g. `8 -------- ... *`.
+fn parse_board(value: &str) -> Result<Othello, ApolloError> {
+    let mut tokens = value.split_whitespace();
+    let size = tokens
+        .next()
+        .and_then(|size| size.parse::<usize>().ok())
+        .ok_or_else(|| ApolloError::Parse(format!("Invalid GGF board '{}'!", value)))?;
+
+    let squares = tokens.collect::<String>();
+    let expected = size
+        .checked_mul(size)
+        .and_then(|squares| squares.checked_add(1));
+    if expected != Some(squares.chars().count()) {
+        return Err(ApolloError::Parse(format!(
+            "Invalid GGF board '{}'!",
+            value
+        )));
+    }
+
+    squares.parse()
+}
+
+/// Parses a move with its optional evaluation and time, e.g. `d3/-1.50/2.01`.
+fn parse_move(tag: &str, value: &str) -> Result<GgfMove, ApolloError> {
+    let mut parts = value.split('/');
+
+    let action = match parts.next().map(str::trim) {
+        Some(pass) if pass.eq_ignore_ascii_case("pa") || pass.eq_ignore_ascii_case("pass") => {
+            Action::Pass
+        }
+        Some(square) => Action::Place(square.parse()?),
+        None => return Err(ApolloError::Parse(format!("Invalid GGF move '{}'!", value))),
+    };
+
+    let eval = parts.next().and_then(|eval| eval.trim().parse().ok());
+
+    // Times are either seconds or minutes and seconds, e.g. "1:05.20"
+    let time = parts.next().and_then(|time| {
+        time.trim().split(':').try_fold(0., |total, part| {
+            part.parse::<f64>().ok().map(|t| total * 60. + t)
+        })
+    });
+
+    Ok(GgfMove {
+        player: if tag == "W" { Disc::White } else { Disc::Black },
+        action,
+        eval,
+        time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn games_round_trip() {
+        let mut game = Othello::new(Board::default());
+        game.record_history();
+        let mut ply = 0;
+        while !game.is_game_over() {
+            let moves = game.legal_moves();
+            game.play(moves[ply * 5 % moves.len()]);
+            ply += 1;
+        }
+
+        let mut record = GgfGame::from_othello(&game).unwrap().unwrap();
+        record.black = Some(String::from("apollo"));
+        let parsed = record.to_string().parse::<GgfGame>().unwrap();
+
+        assert_eq!(parsed.to_string(), record.to_string());
+        assert_eq!(parsed.black.as_deref(), Some("apollo"));
+        assert_eq!(
+            parsed.result,
+            Some(game.result().margin(Disc::Black) as f64)
+        );
+        assert_eq!(parsed.replay().unwrap().to_string(), game.to_string());
+    }
+
+    #[test]
+    fn reversi_games_are_not_recorded() {
+        let reversi = Rules {
+            fill_center: true,
+            ..Rules::OTHELLO
+        };
+        let mut game = Othello::with_rules(Board::new(8, 8), Disc::Black, reversi);
+        game.record_history();
+        game.play(game.legal_moves()[0]);
+
+        assert!(GgfGame::from_othello(&game).unwrap().is_err());
+    }
+
+    #[test]
+    fn non_ascii_boards_are_rejected() {
+        assert!("(;GM[Othello]BO[2 XXXé];)".parse::<GgfGame>().is_err());
+        assert!("(;GM[Othello]BO[2 XXXXé];)".parse::<GgfGame>().is_err());
+    }
+
+    #[test]
+    fn oversized_boards_are_rejected() {
+        assert!("(;GM[Othello]BO[9999999999999 - *];)"
+            .parse::<GgfGame>()
+            .is_err());
+    }
+}
@@ -1,5 +1,13 @@
 mod board;
+mod ggf;
+#[allow(clippy::module_inception)]
 mod othello;
+mod symmetry;
+mod wthor;
+mod zobrist;
 
 pub use board::*;
+pub use ggf::*;
 pub use othello::*;
+pub use symmetry::*;
+pub use wthor::*;
@@ -1,4 +1,6 @@
-use super::{Board, Disc, Position};
+use super::{zobrist, Bitboard, Bits, Board, Disc, DiscSet, Position, Shifts};
+use crate::error::ApolloError;
+use std::{fmt::Display, str::FromStr};
 
 const DIRECTIONS: &[(isize, isize)] = &[
     (0, 1),
@@ -11,128 +13,409 @@ const DIRECTIONS: &[(isize, isize)] = &[
     (-1, 1),
 ];
 
+/// A single turn of the game: either a disc placed on the board,
+/// or a pass when the player to move has no valid moves.
+#[derive(Hash, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    Place(Position),
+    Pass,
+}
+
+/// Displays the square played on, or "pass".
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Place(pos) => write!(f, "{}", pos),
+            Action::Pass => write!(f, "pass"),
+        }
+    }
+}
+
+/// A record of an action taken with `Othello::make_move`, holding
+/// everything needed to take it back with `Othello::unmake_move`.
 #[derive(Clone)]
 pub struct Move {
-    index: usize,
+    action: Action,
     tile: Disc,
+    flipped: DiscSet,
+    hash: u64,
+}
+
+impl Move {
+    pub fn action(&self) -> Action {
+        self.action
+    }
+
+    /// The player who took the action.
+    pub fn player(&self) -> Disc {
+        self.tile
+    }
+
+    /// The discs the action flipped, which is empty for a pass.
+    pub fn flipped(&self) -> DiscSet {
+        self.flipped
+    }
+}
+
+/// The rules a game is played under. The default is standard Othello.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rules {
+    /// Anti-Othello, where the player with fewer discs wins.
+    pub misere: bool,
+    /// Classic Reversi, where the game starts from an empty board and
+    /// the four center squares must be filled before any other move.
+    pub fill_center: bool,
+}
+
+impl Rules {
+    pub const OTHELLO: Rules = Rules {
+        misere: false,
+        fill_center: false,
+    };
+}
+
+/// Displays the rules by name, e.g. "othello" or "reversi+misere".
+impl Display for Rules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.fill_center, self.misere) {
+            (false, false) => write!(f, "othello"),
+            (false, true) => write!(f, "misere"),
+            (true, false) => write!(f, "reversi"),
+            (true, true) => write!(f, "reversi+misere"),
+        }
+    }
+}
+
+/// Parses rules as displayed by `Rules`: any of "othello", "misere" (or "anti")
+/// and "reversi" joined by `+`.
+impl FromStr for Rules {
+    type Err = ApolloError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Rules::OTHELLO;
+        for name in s.split('+') {
+            match name.trim() {
+                "othello" | "standard" => (),
+                "misere" | "anti" => rules.misere = true,
+                "reversi" => rules.fill_center = true,
+                other => return Err(ApolloError::Parse(format!("Unknown rules '{}'!", other))),
+            }
+        }
+        Ok(rules)
+    }
+}
+
+/// Every action taken since the position a game started from.
+#[derive(Clone)]
+struct History {
+    start: Board,
+    start_player: Disc,
+    actions: Vec<Action>,
 }
 
 #[derive(Clone)]
 pub struct Othello {
     board: Board,
+    to_move: Disc,
+    rules: Rules,
+    hash: u64,
+    history: Option<History>,
 }
 
 impl Othello {
+    /// Starts a game from `board` with black to move.
     pub fn new(board: Board) -> Self {
-        Othello { board }
+        Othello::with_player(board, Disc::Black)
     }
 
-    pub fn valid_moves(&self, player: Disc) -> Vec<Position> {
-        let mut moves = Vec::new();
-        for row in 0..self.board.height() {
-            for col in 0..self.board.width() {
-                let pos = Position::new(row as isize, col as isize);
-                if self.is_valid_move(pos, player) {
-                    moves.push(pos);
-                }
+    /// Starts a game from `board` with `player` to move.
+    pub fn with_player(board: Board, player: Disc) -> Self {
+        Othello::with_rules(board, player, Rules::OTHELLO)
+    }
+
+    /// Starts a game from `board` with `player` to move, played under `rules`.
+    pub fn with_rules(board: Board, player: Disc, rules: Rules) -> Self {
+        Othello {
+            hash: zobrist::hash(&board, player),
+            board,
+            to_move: player,
+            rules,
+            history: None,
+        }
+    }
+
+    /// Replays `transcript` from `start`, keeping the history from there.
+    /// Passes are left out of transcripts and are filled back in whenever
+    /// the player to move has no valid moves.
+    pub fn from_transcript(start: Othello, transcript: &str) -> Result<Othello, ApolloError> {
+        let mut game = start;
+        game.record_history();
+
+        for pos in parse_transcript(transcript)? {
+            if !game.board.contains(pos) {
+                return Err(ApolloError::OffBoard(pos));
+            }
+
+            if game.is_legal(Action::Pass) {
+                game.pass();
+            }
+
+            if !game.is_legal(Action::Place(pos)) {
+                return Err(ApolloError::IllegalMove(format!(
+                    "Illegal move '{}' in transcript!",
+                    pos
+                )));
+            }
+
+            game.place(pos);
+        }
+
+        Ok(game)
+    }
+
+    /// Starts keeping a history of every action taken from the current position.
+    pub fn record_history(&mut self) {
+        self.history = Some(History {
+            start: self.board.clone(),
+            start_player: self.to_move,
+            actions: Vec::new(),
+        });
+    }
+
+    /// Every action taken since the history started being kept.
+    pub fn history(&self) -> Option<&[Action]> {
+        self.history.as_ref().map(|history| &history.actions[..])
+    }
+
+    /// The game as it was after the first `ply` actions of its history.
+    pub fn replay(&self, ply: usize) -> Option<Othello> {
+        let history = self.history.as_ref()?;
+        let actions = history.actions.get(..ply)?;
+
+        let mut game = Othello::with_rules(history.start.clone(), history.start_player, self.rules);
+        game.record_history();
+        for action in actions {
+            game.play(*action);
+        }
+
+        Some(game)
+    }
+
+    /// The history as a compact transcript of squares, such as "f5d6c3".
+    pub fn transcript(&self) -> Option<String> {
+        let history = self.history.as_ref()?;
+        let moves = history
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::Place(pos) => Some(*pos),
+                Action::Pass => None,
+            })
+            .collect::<Vec<_>>();
+
+        Some(format_transcript(&moves))
+    }
+
+    /// The player whose turn it is.
+    pub fn to_move(&self) -> Disc {
+        self.to_move
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// A Zobrist hash of the discs on the board and the side to move.
+    /// It is the same from one run to the next, so it can be stored.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Every action the player to move can take. When they have no valid
+    /// moves but their opponent does, the only action is a pass; once the
+    /// game is over there are none.
+    pub fn legal_moves(&self) -> Vec<Action> {
+        let moves = self.moves(self.to_move);
+        if moves != 0 {
+            self.board.positions(moves).map(Action::Place).collect()
+        } else if self.moves(self.to_move.opponent()) != 0 {
+            vec![Action::Pass]
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn is_legal(&self, action: Action) -> bool {
+        match action {
+            Action::Place(pos) => self.is_valid_move(pos, self.to_move),
+            Action::Pass => {
+                self.moves(self.to_move) == 0 && self.moves(self.to_move.opponent()) != 0
             }
         }
-        moves
+    }
+
+    pub fn valid_moves(&self, player: Disc) -> Vec<Position> {
+        self.board.positions(self.moves(player)).collect()
+    }
+
+    /// The number of valid moves `player` has, without listing them.
+    pub fn mobility(&self, player: Disc) -> usize {
+        self.moves(player).count_ones() as usize
+    }
+
+    /// The number of empty squares left to play on.
+    pub fn empties(&self) -> usize {
+        self.board.empty().count_ones() as usize
     }
 
     pub fn is_valid_move(&self, pos: Position, player: Disc) -> bool {
+        if !self.board.contains(pos) {
+            return false;
+        }
+
         // If this isn't empty, of course we can't move here
-        if self.board.at(pos) != Disc::Empty {
+        let mv = 1 << self.board.index(pos);
+        if self.board.empty() & mv == 0 {
             return false;
         }
 
-        DIRECTIONS
-            .iter()
-            .any(|direction| self.is_valid_move_dir(pos, player, *direction))
+        if let Some(center) = self.open_center() {
+            return center & mv != 0;
+        }
+
+        self.flip_bits(mv, player) != 0
     }
 
-    fn is_valid_move_dir(&self, pos: Position, player: Disc, (dr, dc): (isize, isize)) -> bool {
-        let mut step = 1;
+    /// The discs that `player` would flip by moving to `pos`, without
+    /// making the move. It is empty if the move is not valid.
+    pub fn flips(&self, pos: Position, player: Disc) -> DiscSet {
+        let flips = if self.is_valid_move(pos, player) {
+            self.flip_bits(1 << self.board.index(pos), player)
+        } else {
+            0
+        };
 
-        // The opposite color must be between this coordinate and another of the same disc
-        let between = player.opponent();
-        let mut between_satisfied = false;
+        DiscSet::new(flips, self.board.width())
+    }
 
-        loop {
-            let new_row = pos.row + step * dr;
-            let new_col = pos.col + step * dc;
+    /// The empty center squares, while they still have to be filled
+    /// before anything else can be played under Reversi rules.
+    fn open_center(&self) -> Option<Bits> {
+        if !self.rules.fill_center {
+            return None;
+        }
 
-            if new_row < 0
-                || new_col < 0
-                || new_row >= self.board.height() as isize
-                || new_col >= self.board.width() as isize
-            {
-                return false;
-            }
+        let open = self.board.center() & self.board.empty();
+        if open != 0 {
+            Some(open)
+        } else {
+            None
+        }
+    }
 
-            // println!("new_row = {}, new_col = {}", new_row, new_col);
-            let new_pos = Position::new(new_row, new_col);
-
-            let disc = self.board.at(new_pos);
-
-            if disc == Disc::Empty {
-                // If we get to an empty disc, we can't move here
-                // It's either the edge or an empty tile
-                return false;
-            } else if disc == between {
-                // The between part is satisfied, so let's see if we can find one of the same disc
-                between_satisfied = true;
-            } else {
-                if between_satisfied {
-                    return true;
-                } else {
-                    return false;
-                }
-            }
+    /// Every square `player` can move to.
+    fn moves(&self, player: Disc) -> Bits {
+        if let Some(center) = self.open_center() {
+            return center;
+        }
 
-            step += 1;
+        let own = self.board.discs(player);
+        let between = self.board.discs(player.opponent());
+        let empty = self.board.empty();
+
+        if self.board.is_narrow() {
+            moves_on(&self.board.shifts::<u64>(), own, between, empty)
+        } else {
+            moves_on(&self.board.shifts::<Bits>(), own, between, empty)
         }
     }
 
-    pub fn place(&mut self, pos: Position, player: Disc) {
-        debug_assert!(self.is_valid_move(pos, player));
-        self.board.set(pos, player);
-        for dir in DIRECTIONS.iter() {
-            if self.is_valid_move_dir(pos, player, *dir) {
-                self.place_dir(pos, player, *dir);
-            }
+    /// The discs that `player` would flip by moving to the square `mv`.
+    fn flip_bits(&self, mv: Bits, player: Disc) -> Bits {
+        let own = self.board.discs(player);
+        let between = self.board.discs(player.opponent());
+        flips_between(&self.board, mv, own, between)
+    }
+
+    /// Takes `action` for the player to move, which must be legal.
+    pub fn play(&mut self, action: Action) {
+        self.make_move(action);
+    }
+
+    /// Takes `action` for the player to move if it is legal, returning
+    /// a record that can be given to `unmake_move` to take it back.
+    pub fn try_play(&mut self, action: Action) -> Result<Move, ApolloError> {
+        match action {
+            Action::Place(pos) if !self.board.contains(pos) => Err(ApolloError::OffBoard(pos)),
+            action if !self.is_legal(action) => Err(ApolloError::IllegalMove(format!(
+                "Illegal move '{}'!",
+                action
+            ))),
+            action => Ok(self.make_move(action)),
         }
     }
 
-    fn place_dir(&mut self, pos: Position, player: Disc, (dr, dc): (isize, isize)) {
-        let mut step = 1;
+    /// Places a disc for the player to move and hands the turn to their
+    /// opponent, returning the discs that were flipped. The move must be legal.
+    pub fn place(&mut self, pos: Position) -> DiscSet {
+        self.make_move(Action::Place(pos)).flipped
+    }
 
-        // The opposite color must be between this coordinate and another of the same disc
-        let between = player.opponent();
+    pub fn try_place(&mut self, pos: Position) -> Result<DiscSet, ApolloError> {
+        Ok(self.try_play(Action::Place(pos))?.flipped)
+    }
 
-        loop {
-            let new_row = pos.row + step * dr;
-            let new_col = pos.col + step * dc;
+    /// Hands the turn to the opponent without moving.
+    pub fn pass(&mut self) {
+        self.make_move(Action::Pass);
+    }
+
+    /// Takes `action` for the player to move, returning a record that can
+    /// be given to `unmake_move` to take it back. The action must be legal;
+    /// see `try_play`.
+    pub fn make_move(&mut self, action: Action) -> Move {
+        let player = self.to_move;
+        debug_assert!(self.is_legal(action));
 
-            if new_row < 0
-                || new_col < 0
-                || new_row >= self.board.height() as isize
-                || new_col >= self.board.width() as isize
-            {
-                break;
+        let hash = self.hash;
+        let (index, flipped) = match action {
+            Action::Place(pos) => {
+                let index = self.board.index(pos);
+                let flips = self.flip_bits(1 << index, player);
+                self.board.flip((1 << index) | flips, player);
+                (Some(index), flips)
             }
+            Action::Pass => (None, 0),
+        };
 
-            // println!("new_row = {}, new_col = {}", new_row, new_col);
-            let new_pos = Position::new(new_row, new_col);
-            let disc = self.board.at(new_pos);
+        self.to_move = player.opponent();
+        self.hash ^= zobrist::update(player, index, flipped);
 
-            if disc == between {
-                self.board.set(new_pos, player);
-            } else {
-                break;
-            }
+        if let Some(history) = &mut self.history {
+            history.actions.push(action);
+        }
 
-            step += 1;
+        Move {
+            action,
+            tile: player,
+            flipped: DiscSet::new(flipped, self.board.width()),
+            hash,
+        }
+    }
+
+    /// Restores the position from before `mv` was made. Moves must be
+    /// unmade in the reverse order they were made.
+    pub fn unmake_move(&mut self, mv: Move) {
+        if let Action::Place(pos) = mv.action {
+            self.board.flip(mv.flipped.bits(), mv.tile.opponent());
+            self.board.set(pos, Disc::Empty);
+        }
+
+        self.to_move = mv.tile;
+        self.hash = mv.hash;
+
+        if let Some(history) = &mut self.history {
+            history.actions.pop();
         }
     }
 
@@ -140,40 +423,343 @@ impl Othello {
         &self.board
     }
 
-    pub fn any_valid_moves(&self) -> bool {
-        for row in 0..self.board.height() {
-            for col in 0..self.board.width() {
-                let pos = Position::new(row as isize, col as isize);
+    /// The game is over once neither player can move.
+    pub fn is_game_over(&self) -> bool {
+        self.moves(Disc::Black) | self.moves(Disc::White) == 0
+    }
 
-                if self.is_valid_move(pos, Disc::Black) || self.is_valid_move(pos, Disc::White) {
-                    return true;
-                }
-            }
+    /// The disc counts on the board, scored as though the game ended here.
+    pub fn result(&self) -> GameResult {
+        GameResult {
+            black: self.board.discs(Disc::Black).count_ones() as usize,
+            white: self.board.discs(Disc::White).count_ones() as usize,
+            empty: self.board.empty().count_ones() as usize,
+            misere: self.rules.misere,
         }
-        false
     }
 
+    /// The winner under the game's rules, or `None` if the game is a draw.
     pub fn winner(&self) -> Option<Disc> {
-        let mut white = 0;
-        let mut black = 0;
-        for row in 0..self.board.height() {
-            for col in 0..self.board.width() {
-                let pos = Position::new(row as isize, col as isize);
-                let disc = self.board.at(pos);
-                if disc == Disc::White {
-                    white += 1;
-                } else if disc == Disc::Black {
-                    black += 1;
-                }
-            }
+        match self.result().outcome() {
+            Outcome::Win(player) => Some(player),
+            Outcome::Draw => None,
+        }
+    }
+}
+
+/// The discs in `between` that a disc placed on the square `mv` would flip,
+/// for the player with the discs in `own`. Searches that keep their own
+/// discs rather than a whole game can use it to make moves.
+pub(crate) fn flips_between(board: &Board, mv: Bits, own: Bits, between: Bits) -> Bits {
+    if board.is_narrow() {
+        flips_on(&board.shifts::<u64>(), mv, own, between)
+    } else {
+        flips_on(&board.shifts::<Bits>(), mv, own, between)
+    }
+}
+
+/// The empty squares in `empty` that the player with the discs in `own`
+/// can move to, generated on bitboards of type `B`.
+fn moves_on<B: Bitboard>(shifts: &Shifts<B>, own: Bits, between: Bits, empty: Bits) -> Bits {
+    let (own, between, empty) = (B::narrow(own), B::narrow(between), B::narrow(empty));
+
+    let mut moves = B::ZERO;
+    for dir in DIRECTIONS.iter() {
+        // Walk along runs of the opposite color starting next to our own discs;
+        // an empty square just past the end of a run is a valid move
+        let mut run = shifts.shift(own, *dir) & between;
+        while run != B::ZERO {
+            let next = shifts.shift(run, *dir);
+            moves |= next & empty;
+            run = next & between;
+        }
+    }
+    moves.widen()
+}
+
+/// The discs in `between` flipped by a disc placed on `mv`, found on bitboards of type `B`.
+fn flips_on<B: Bitboard>(shifts: &Shifts<B>, mv: Bits, own: Bits, between: Bits) -> Bits {
+    let (mv, own, between) = (B::narrow(mv), B::narrow(own), B::narrow(between));
+
+    let mut flips = B::ZERO;
+    for dir in DIRECTIONS.iter() {
+        // The opposite color must be between this square and another of the same disc
+        let mut line = B::ZERO;
+        let mut next = shifts.shift(mv, *dir);
+        while next & between != B::ZERO {
+            line |= next;
+            next = shifts.shift(next, *dir);
         }
 
-        if white > black {
-            Some(Disc::White)
-        } else if black > white {
-            Some(Disc::Black)
+        if next & own != B::ZERO {
+            flips |= line;
+        }
+    }
+    flips.widen()
+}
+
+/// Who won a game, if anyone.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Win(Disc),
+    Draw,
+}
+
+/// The disc counts at the end of a game.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct GameResult {
+    pub black: usize,
+    pub white: usize,
+    pub empty: usize,
+    /// Whether the player with fewer discs wins.
+    pub misere: bool,
+}
+
+impl GameResult {
+    pub fn outcome(&self) -> Outcome {
+        let (more, fewer) = if self.misere {
+            (Disc::White, Disc::Black)
         } else {
-            None
+            (Disc::Black, Disc::White)
+        };
+
+        if self.black > self.white {
+            Outcome::Win(more)
+        } else if self.white > self.black {
+            Outcome::Win(fewer)
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.outcome() == Outcome::Draw
+    }
+
+    /// The final score of `player`. As in tournament rules, empty squares
+    /// are awarded to the player with more discs (the winner, unless playing
+    /// misère), or split evenly in a draw.
+    pub fn score(&self, player: Disc) -> usize {
+        let (own, other) = match player {
+            Disc::Black => (self.black, self.white),
+            Disc::White => (self.white, self.black),
+            Disc::Empty => return self.empty,
+        };
+
+        if own > other {
+            own + self.empty
+        } else if own == other {
+            own + self.empty / 2
+        } else {
+            own
+        }
+    }
+
+    /// The final disc differential from `player`'s point of view, which
+    /// is positive when they won. Under misère rules it is the number of
+    /// discs they were behind by.
+    pub fn margin(&self, player: Disc) -> isize {
+        let margin = self.score(player) as isize - self.score(player.opponent()) as isize;
+        if self.misere {
+            -margin
+        } else {
+            margin
+        }
+    }
+}
+
+/// Displays the final score, e.g. "Black wins 40-24" or "Draw 32-32".
+impl Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (black, white) = (self.score(Disc::Black), self.score(Disc::White));
+        match self.outcome() {
+            Outcome::Win(Disc::White) => write!(f, "White wins {}-{}", white, black),
+            Outcome::Win(_) => write!(f, "Black wins {}-{}", black, white),
+            Outcome::Draw => write!(f, "Draw {}-{}", black, white),
+        }
+    }
+}
+
+/// Displays the game as a one-line position: the board as written by
+/// `Board::to_position_string`, followed by `X` or `O` for the side to move.
+impl Display for Othello {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.board.to_position_string(),
+            self.to_move.to_char()
+        )
+    }
+}
+
+/// Parses a one-line position as displayed by `Othello`. The side to move
+/// may be left off, in which case black moves first.
+impl FromStr for Othello {
+    type Err = ApolloError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut squares = s.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+
+        // A board always has a square number of squares, so one extra is the side to move
+        let size = (squares.len() as f64).sqrt() as usize;
+        if size * size == squares.len() {
+            let board = squares.into_iter().collect::<String>();
+            return Ok(Othello::new(board.parse()?));
+        }
+
+        let player = squares.pop();
+        let size = (squares.len() as f64).sqrt() as usize;
+        if size * size != squares.len() {
+            return Err(ApolloError::Parse(format!(
+                "A position must be a square board and the side to move, but found {} squares!",
+                squares.len() + 1
+            )));
+        }
+
+        let player = match player.map(Disc::from_char) {
+            Some(Ok(player)) if player != Disc::Empty => player,
+            _ => {
+                return Err(ApolloError::Parse(format!(
+                    "Invalid side to move '{}' in position!",
+                    player.map(String::from).unwrap_or_default()
+                )))
+            }
+        };
+
+        let board = squares.into_iter().collect::<String>();
+        Ok(Othello::with_player(board.parse()?, player))
+    }
+}
+
+/// Splits a transcript of concatenated squares, such as "f5d6c3d3c4",
+/// into its moves. Whitespace between squares is ignored.
+pub fn parse_transcript(transcript: &str) -> Result<Vec<Position>, ApolloError> {
+    let mut moves = Vec::new();
+    let mut rest = transcript.trim_start();
+
+    while !rest.is_empty() {
+        // Each square is one letter followed by its row number
+        let len = rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| !c.is_ascii_digit())
+            .map(|(i, _)| i)
+            .unwrap_or_else(|| rest.len());
+
+        let (square, tail) = rest.split_at(len);
+        moves.push(square.parse::<Position>()?);
+        rest = tail.trim_start();
+    }
+
+    Ok(moves)
+}
+
+/// Joins moves into a transcript of concatenated squares, such as "f5d6c3d3c4".
+pub fn format_transcript(moves: &[Position]) -> String {
+    moves.iter().map(|pos| pos.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays a game to the end, picking each move by the ply it is played on.
+    fn play_out(mut game: Othello) -> Othello {
+        game.record_history();
+        let mut ply = 0;
+        while !game.is_game_over() {
+            let moves = game.legal_moves();
+            game.play(moves[ply * 7 % moves.len()]);
+            ply += 1;
+        }
+        game
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        let mut board = Board::with_size(6);
+        board.block(Position::new(0, 0));
+        let game = play_out(Othello::new(board));
+
+        for ply in 0..game.history().unwrap().len() {
+            let position = game.replay(ply).unwrap().to_string();
+            let parsed = position.parse::<Othello>().unwrap();
+            assert_eq!(parsed.to_string(), position);
+        }
+    }
+
+    #[test]
+    fn positions_reject_non_ascii_squares() {
+        assert!("XXXé".parse::<Othello>().is_err());
+        assert!("XXXXé".parse::<Othello>().is_err());
+        assert!("XXXXXXXXé".parse::<Othello>().is_err());
+    }
+
+    #[test]
+    fn transcripts_parse_to_positions() {
+        let moves = parse_transcript("f5 D6\nc3").unwrap();
+        assert!(moves[1] == Position::new(5, 3));
+        assert_eq!(format_transcript(&moves), "f5d6c3");
+
+        assert!(parse_transcript("f5d").is_err());
+        assert!(parse_transcript("f5x0").is_err());
+    }
+
+    #[test]
+    fn transcripts_round_trip() {
+        let reversi = Rules {
+            fill_center: true,
+            ..Rules::OTHELLO
+        };
+        let mut midgame = Othello::new(Board::default());
+        for square in &["f5", "f6", "e6"] {
+            midgame.place(square.parse().unwrap());
+        }
+
+        let starts = vec![
+            Othello::new(Board::default()),
+            Othello::with_player(Board::with_size(6), Disc::White),
+            Othello::with_rules(Board::new(8, 8), Disc::Black, reversi),
+            midgame,
+        ];
+        for start in starts {
+            let game = play_out(start.clone());
+            let transcript = game.transcript().unwrap();
+            let replayed = Othello::from_transcript(start, &transcript).unwrap();
+
+            assert_eq!(replayed.to_string(), game.to_string());
+            assert_eq!(replayed.transcript().unwrap(), transcript);
+            assert!(replayed.history() == game.history());
+        }
+    }
+
+    #[test]
+    fn transcripts_reject_illegal_moves() {
+        let start = Othello::new(Board::default());
+        assert!(Othello::from_transcript(start.clone(), "f5f5").is_err());
+        assert!(Othello::from_transcript(start, "a1").is_err());
+    }
+
+    #[test]
+    fn narrow_and_wide_bitboards_agree() {
+        let game = play_out(Othello::new(Board::default()));
+        for ply in 0..game.history().unwrap().len() {
+            let game = game.replay(ply).unwrap();
+            let board = game.board();
+            let (narrow, wide) = (board.shifts::<u64>(), board.shifts::<Bits>());
+            let own = board.discs(game.to_move());
+            let between = board.discs(game.to_move().opponent());
+
+            let moves = moves_on(&narrow, own, between, board.empty());
+            assert_eq!(moves, moves_on(&wide, own, between, board.empty()));
+            for pos in board.positions(moves) {
+                let mv = 1 << board.index(pos);
+                assert_eq!(
+                    flips_on(&narrow, mv, own, between),
+                    flips_on(&wide, mv, own, between)
+                );
+            }
         }
     }
 }
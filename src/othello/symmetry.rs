@@ -0,0 +1,208 @@
+use super::{Board, Disc, Position};
+
+/// One of the 8 symmetries of a square board: the rotations
+/// (clockwise) and the reflections.
+#[derive(Hash, Copy, Clone, PartialEq, Eq)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirrors the columns, swapping a1 and h1.
+    FlipHorizontal,
+    /// Mirrors the rows, swapping a1 and a8.
+    FlipVertical,
+    /// Reflects across the a1-h8 diagonal.
+    FlipDiagonal,
+    /// Reflects across the h1-a8 diagonal.
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    /// The symmetry that undoes this one.
+    pub fn inverse(&self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => *other,
+        }
+    }
+
+    /// Whether this symmetry swaps the width and height of a board.
+    fn transposes(&self) -> bool {
+        matches!(
+            self,
+            Symmetry::Rotate90
+                | Symmetry::Rotate270
+                | Symmetry::FlipDiagonal
+                | Symmetry::FlipAntiDiagonal
+        )
+    }
+}
+
+impl Position {
+    /// Where this position ends up when `symmetry` is applied
+    /// to a board with the given width and height.
+    pub fn transform(&self, symmetry: Symmetry, width: usize, height: usize) -> Position {
+        let (row, col) = (self.row, self.col);
+        let last_row = height as isize - 1;
+        let last_col = width as isize - 1;
+
+        match symmetry {
+            Symmetry::Identity => Position::new(row, col),
+            Symmetry::Rotate90 => Position::new(col, last_row - row),
+            Symmetry::Rotate180 => Position::new(last_row - row, last_col - col),
+            Symmetry::Rotate270 => Position::new(last_col - col, row),
+            Symmetry::FlipHorizontal => Position::new(row, last_col - col),
+            Symmetry::FlipVertical => Position::new(last_row - row, col),
+            Symmetry::FlipDiagonal => Position::new(col, row),
+            Symmetry::FlipAntiDiagonal => Position::new(last_col - col, last_row - row),
+        }
+    }
+}
+
+impl Board {
+    /// The board with `symmetry` applied to every square.
+    pub fn transform(&self, symmetry: Symmetry) -> Board {
+        let mut board = if symmetry.transposes() {
+            Board::new(self.height(), self.width())
+        } else {
+            Board::new(self.width(), self.height())
+        };
+
+        for player in [Disc::Black, Disc::White].iter() {
+            for pos in self.positions(self.discs(*player)) {
+                board.set(
+                    pos.transform(symmetry, self.width(), self.height()),
+                    *player,
+                );
+            }
+        }
+
+        for pos in self.blocked().iter() {
+            board.block(pos.transform(symmetry, self.width(), self.height()));
+        }
+
+        board
+    }
+
+    /// The canonical form of this board, which is the same for every board
+    /// it is symmetric to, along with the symmetry that produced it from
+    /// this board. Only symmetries that keep the board's shape are considered.
+    pub fn canonical(&self) -> (Board, Symmetry) {
+        Symmetry::ALL
+            .iter()
+            .filter(|symmetry| !symmetry.transposes() || self.width() == self.height())
+            .map(|symmetry| (self.transform(*symmetry), *symmetry))
+            .min_by_key(|(board, _)| {
+                (
+                    board.discs(Disc::Black),
+                    board.discs(Disc::White),
+                    board.blocked().bits(),
+                )
+            })
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::othello::{Action, Othello};
+    use std::collections::HashSet;
+
+    /// Every position of a game played from `board`, with moves
+    /// picked by the ply they are played on.
+    fn positions(board: Board) -> Vec<Othello> {
+        let mut game = Othello::new(board);
+        let mut positions = vec![game.clone()];
+        let mut ply = 0;
+        while !game.is_game_over() {
+            let moves = game.legal_moves();
+            game.play(moves[ply * 5 % moves.len()]);
+            positions.push(game.clone());
+            ply += 1;
+        }
+        positions
+    }
+
+    fn boards() -> Vec<Board> {
+        let mut blocked = Board::with_size(6);
+        blocked.block(Position::new(0, 1));
+        let mut wide = Board::new(6, 4);
+        wide.set(Position::new(1, 2), Disc::Black);
+        wide.set(Position::new(2, 2), Disc::White);
+        wide.set(Position::new(2, 3), Disc::Black);
+
+        vec![Board::default(), blocked, wide]
+    }
+
+    #[test]
+    fn inverses_undo_symmetries() {
+        for start in boards() {
+            for game in positions(start) {
+                let board = game.board();
+                for symmetry in Symmetry::ALL.iter() {
+                    let back = board.transform(*symmetry).transform(symmetry.inverse());
+                    assert!(back == *board, "{}", game);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_boards_share_a_canonical_form() {
+        for start in boards() {
+            for game in positions(start) {
+                let board = game.board();
+                let (canonical, symmetry) = board.canonical();
+                assert!(board.transform(symmetry) == canonical, "{}", game);
+
+                for symmetry in Symmetry::ALL.iter() {
+                    if symmetry.transposes() && board.width() != board.height() {
+                        continue;
+                    }
+                    let image = board.transform(*symmetry);
+                    assert!(image.canonical().0 == canonical, "{}", game);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn moves_map_onto_moves() {
+        for start in boards() {
+            for game in positions(start) {
+                let (width, height) = (game.board().width(), game.board().height());
+                for symmetry in Symmetry::ALL.iter() {
+                    let image =
+                        Othello::with_player(game.board().transform(*symmetry), game.to_move());
+
+                    let moves = game
+                        .legal_moves()
+                        .into_iter()
+                        .map(|action| match action {
+                            Action::Place(pos) => {
+                                Action::Place(pos.transform(*symmetry, width, height))
+                            }
+                            Action::Pass => Action::Pass,
+                        })
+                        .collect::<HashSet<_>>();
+                    let expected = image.legal_moves().into_iter().collect::<HashSet<_>>();
+                    assert!(moves == expected, "{}", game);
+                }
+            }
+        }
+    }
+}
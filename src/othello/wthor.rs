@@ -0,0 +1,217 @@
+use super::{Action, Board, Othello, Position};
+use crate::error::ApolloError;
+
+// Every WTHOR file starts with a header describing its contents
+const HEADER_LEN: usize = 16;
+const GAME_LEN: usize = 68;
+const PLAYER_LEN: usize = 20;
+const TOURNAMENT_LEN: usize = 26;
+const MAX_MOVES: usize = 60;
+
+/// A game from a WTHOR database, the binary format the standard
+/// corpus of tournament Othello games is distributed in.
+#[derive(Clone)]
+pub struct WthorGame {
+    pub year: u16,
+    pub tournament: String,
+    pub black: String,
+    pub white: String,
+    /// The number of discs black had at the end of the game.
+    pub black_score: u8,
+    /// The number of discs black would have had with perfect play
+    /// from the point the database solved the game.
+    pub theoretical_score: u8,
+    pub moves: Vec<Position>,
+}
+
+impl WthorGame {
+    /// Plays every move from the standard opening, keeping the history.
+    /// Passes are not recorded in WTHOR games, so they are filled back in
+    /// whenever the player to move has no valid moves.
+    pub fn replay(&self) -> Result<Othello, ApolloError> {
+        let mut game = Othello::new(Board::default());
+        game.record_history();
+
+        for pos in self.moves.iter() {
+            if game.is_legal(Action::Pass) {
+                game.pass();
+            }
+
+            if !game.is_legal(Action::Place(*pos)) {
+                return Err(ApolloError::IllegalMove(format!(
+                    "Illegal move '{}' in WTHOR game!",
+                    pos
+                )));
+            }
+
+            game.place(*pos);
+        }
+
+        Ok(game)
+    }
+}
+
+/// Reads every game in a `.wtb` database, looking up player names in the
+/// contents of a `.jou` file and tournament names in a `.trn` file.
+pub fn read_wthor(wtb: &[u8], jou: &[u8], trn: &[u8]) -> Result<Vec<WthorGame>, ApolloError> {
+    let players = read_wthor_names(jou, PLAYER_LEN)?;
+    let tournaments = read_wthor_names(trn, TOURNAMENT_LEN)?;
+    read_wthor_games(wtb, &players, &tournaments)
+}
+
+/// Reads every game in a `.wtb` database.
+pub fn read_wthor_games(
+    wtb: &[u8],
+    players: &[String],
+    tournaments: &[String],
+) -> Result<Vec<WthorGame>, ApolloError> {
+    let header = wtb
+        .get(..HEADER_LEN)
+        .ok_or_else(|| ApolloError::Parse(String::from("WTHOR database is missing its header!")))?;
+
+    // Only 8x8 boards are stored in 68 byte records; 0 also means 8x8 in older files
+    let size = header[12];
+    if size != 0 && size != 8 {
+        return Err(ApolloError::BoardSize(size as usize, size as usize));
+    }
+
+    let count = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let year = u16::from_le_bytes([header[10], header[11]]);
+    let name = |names: &[String], index: u16| {
+        names
+            .get(index as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("#{}", index))
+    };
+
+    wtb[HEADER_LEN..]
+        .chunks_exact(GAME_LEN)
+        .take(count)
+        .map(|record| {
+            let moves = record[8..8 + MAX_MOVES]
+                .iter()
+                .take_while(|mv| **mv != 0)
+                .map(|mv| read_wthor_move(*mv))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(WthorGame {
+                year,
+                tournament: name(tournaments, u16::from_le_bytes([record[0], record[1]])),
+                black: name(players, u16::from_le_bytes([record[2], record[3]])),
+                white: name(players, u16::from_le_bytes([record[4], record[5]])),
+                black_score: record[6],
+                theoretical_score: record[7],
+                moves,
+            })
+        })
+        .collect()
+}
+
+/// Reads the names in a `.jou` (players, 20 bytes each)
+/// or `.trn` (tournaments, 26 bytes each) file.
+pub fn read_wthor_names(bytes: &[u8], len: usize) -> Result<Vec<String>, ApolloError> {
+    let names = bytes.get(HEADER_LEN..).ok_or_else(|| {
+        ApolloError::Parse(String::from("WTHOR name file is missing its header!"))
+    })?;
+
+    let names = names
+        .chunks_exact(len)
+        .map(|name| {
+            // Names are NUL-terminated Latin-1 strings
+            name.iter()
+                .take_while(|c| **c != 0)
+                .map(|c| *c as char)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .collect();
+
+    Ok(names)
+}
+
+/// Moves are stored as `10 * row + col`, counting from 1, so "a1" is 11 and "h8" is 88.
+fn read_wthor_move(mv: u8) -> Result<Position, ApolloError> {
+    let (row, col) = (mv / 10, mv % 10);
+    if !(1..=8).contains(&row) || !(1..=8).contains(&col) {
+        return Err(ApolloError::Parse(format!("Invalid WTHOR move {}!", mv)));
+    }
+
+    Ok(Position::new(row as isize - 1, col as isize - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::othello::{format_transcript, Disc};
+
+    /// The shortest game there is: black wipes white out in nine moves.
+    const WIPEOUT: &str = "d3c3b3d2e1d6d7e3f4";
+
+    fn header(count: u32, year: u16, size: u8) -> Vec<u8> {
+        let mut header = vec![20, 5, 10, 1];
+        header.extend_from_slice(&count.to_le_bytes());
+        header.extend_from_slice(&[0, 0]);
+        header.extend_from_slice(&year.to_le_bytes());
+        header.extend_from_slice(&[size, 0, 22, 0]);
+        header
+    }
+
+    fn names(names: &[&str], len: usize) -> Vec<u8> {
+        let mut bytes = header(0, 0, 0);
+        for name in names {
+            let mut name = name.as_bytes().to_vec();
+            name.resize(len, 0);
+            bytes.extend(name);
+        }
+        bytes
+    }
+
+    fn database(size: u8, moves: &[u8]) -> Vec<u8> {
+        let mut wtb = header(1, 2005, size);
+        wtb.extend_from_slice(&1u16.to_le_bytes());
+        wtb.extend_from_slice(&0u16.to_le_bytes());
+        wtb.extend_from_slice(&1u16.to_le_bytes());
+        wtb.extend_from_slice(&[64, 64]);
+        let mut moves = moves.to_vec();
+        moves.resize(MAX_MOVES, 0);
+        wtb.extend(moves);
+        wtb
+    }
+
+    #[test]
+    fn reads_a_game() {
+        let wtb = database(8, &[34, 33, 32, 24, 15, 64, 74, 35, 46]);
+        let jou = names(&["Alice", "Bob"], PLAYER_LEN);
+        let trn = names(&["Open", "Cup"], TOURNAMENT_LEN);
+
+        let games = read_wthor(&wtb, &jou, &trn).unwrap();
+        assert_eq!(games.len(), 1);
+
+        let game = &games[0];
+        assert_eq!(game.year, 2005);
+        assert_eq!(game.tournament, "Cup");
+        assert_eq!(game.black, "Alice");
+        assert_eq!(game.white, "Bob");
+        assert_eq!(game.black_score, 64);
+        assert_eq!(format_transcript(&game.moves), WIPEOUT);
+
+        let end = game.replay().unwrap();
+        assert!(end.is_game_over());
+        assert_eq!(end.transcript().unwrap(), WIPEOUT);
+        assert_eq!(end.result().score(Disc::Black), game.black_score as usize);
+    }
+
+    #[test]
+    fn rejects_bad_databases() {
+        let jou = names(&[], PLAYER_LEN);
+        let trn = names(&[], TOURNAMENT_LEN);
+
+        assert!(read_wthor(&[0; 8], &jou, &trn).is_err());
+        assert!(read_wthor(&database(10, &[34]), &jou, &trn).is_err());
+        assert!(read_wthor(&database(8, &[39]), &jou, &trn).is_err());
+        assert!(read_wthor(&database(8, &[34, 34]), &jou, &trn).unwrap()[0]
+            .replay()
+            .is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use super::{Bits, Board, Disc};
+
+const SQUARES: usize = Bits::BITS as usize;
+
+// The keys are generated from a fixed seed so hashes are stable across runs
+// and can be persisted to disk
+const SEED: u64 = 0x6170_6f6c_6c6f_2121;
+
+/// One key per square for each color, followed by the key for white to move.
+static KEYS: ([[u64; SQUARES]; 2], u64) = keys();
+
+/// The SplitMix64 generator, returning its next state and output.
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn keys() -> ([[u64; SQUARES]; 2], u64) {
+    let mut keys = [[0; SQUARES]; 2];
+    let mut state = SEED;
+
+    let mut color = 0;
+    while color < 2 {
+        let mut square = 0;
+        while square < SQUARES {
+            let (next, key) = splitmix64(state);
+            keys[color][square] = key;
+            state = next;
+            square += 1;
+        }
+        color += 1;
+    }
+
+    let (_, white_to_move) = splitmix64(state);
+    (keys, white_to_move)
+}
+
+fn key(player: Disc, index: usize) -> u64 {
+    match player {
+        Disc::Black => KEYS.0[0][index],
+        Disc::White => KEYS.0[1][index],
+        Disc::Empty => 0,
+    }
+}
+
+/// Hashes every disc on `board` along with the side to move.
+pub(crate) fn hash(board: &Board, to_move: Disc) -> u64 {
+    let mut hash = if to_move == Disc::White { KEYS.1 } else { 0 };
+    for player in [Disc::Black, Disc::White].iter() {
+        let mut discs = board.discs(*player);
+        while discs != 0 {
+            hash ^= key(*player, discs.trailing_zeros() as usize);
+            discs &= discs - 1;
+        }
+    }
+    hash
+}
+
+/// The change in hash when `player` places a disc on the square `index`
+/// (or passes, if there is none), flipping the discs in `flipped`.
+pub(crate) fn update(player: Disc, index: Option<usize>, mut flipped: Bits) -> u64 {
+    let mut update = KEYS.1;
+
+    if let Some(index) = index {
+        update ^= key(player, index);
+    }
+
+    while flipped != 0 {
+        let index = flipped.trailing_zeros() as usize;
+        update ^= key(Disc::Black, index) ^ key(Disc::White, index);
+        flipped &= flipped - 1;
+    }
+
+    update
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::othello::{Action, Othello, Rules};
+
+    #[test]
+    fn incremental_hashes_match_full_hashes() {
+        let reversi = Rules {
+            fill_center: true,
+            ..Rules::OTHELLO
+        };
+        let starts = vec![
+            Othello::new(Board::default()),
+            Othello::with_rules(Board::new(8, 8), Disc::Black, reversi),
+            Othello::new(Board::with_size(6)),
+            Othello::new(Board::with_size(10)),
+        ];
+
+        let mut passes = 0;
+        for start in starts {
+            for seed in 1..4 {
+                let mut game = start.clone();
+                let mut ply = 0;
+                while !game.is_game_over() {
+                    let before = game.hash();
+                    assert_eq!(before, hash(game.board(), game.to_move()));
+
+                    let moves = game.legal_moves();
+                    for action in moves.iter() {
+                        let mv = game.make_move(*action);
+                        assert_eq!(game.hash(), hash(game.board(), game.to_move()));
+                        game.unmake_move(mv);
+                        assert_eq!(game.hash(), before);
+                    }
+
+                    if moves == [Action::Pass] {
+                        passes += 1;
+                    }
+                    game.play(moves[(ply * seed + seed / 2) % moves.len()]);
+                    ply += 1;
+                }
+                assert_eq!(game.hash(), hash(game.board(), game.to_move()));
+            }
+        }
+        assert!(passes > 0);
+    }
+
+    // Any change to the keys would make hashes stored by an earlier build useless
+    #[test]
+    fn hashes_are_stable() {
+        let mut game = Othello::new(Board::default());
+        assert_eq!(game.hash(), 0x0da2_b9eb_8c7f_464c);
+        game.place("f5".parse().unwrap());
+        assert_eq!(game.hash(), 0x571c_c0f6_51c6_b434);
+    }
+}
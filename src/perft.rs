@@ -0,0 +1,95 @@
+use crate::{common::*, error::ApolloError, othello::Othello};
+use clap::ArgMatches;
+use std::time::SystemTime;
+
+pub fn main(matches: &ArgMatches) -> Result<(), ApolloError> {
+    let depth = matches.value_of("depth").unwrap().parse::<usize>()?;
+
+    let mut game = extract_game(
+        matches.value_of("position"),
+        matches.value_of("size"),
+        matches.value_of("rules"),
+        matches.value_of("blocked"),
+    )?;
+
+    eprintln!("Counting leaf nodes to depth {}", depth);
+    eprintln!(" starting position: {}", game);
+    eprintln!(" rules: {}", game.rules());
+
+    if matches.is_present("divide") {
+        divide(&mut game, depth);
+        return Ok(());
+    }
+
+    println!("depth,nodes,time,nodes per second");
+    for depth in 1..(depth + 1) {
+        let start = SystemTime::now();
+        let nodes = perft(&mut game, depth);
+        let secs = start.elapsed().unwrap().as_secs_f64();
+
+        println!(
+            "{},{},{:.3},{:.0}",
+            depth,
+            nodes,
+            secs,
+            nodes as f64 / secs.max(f64::EPSILON)
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints the number of leaf nodes under each of the root's actions.
+fn divide(game: &mut Othello, depth: usize) {
+    let mut total = 0;
+    for action in game.legal_moves() {
+        let mv = game.make_move(action);
+        let nodes = perft(game, depth.saturating_sub(1));
+        game.unmake_move(mv);
+
+        println!("{}: {}", action, nodes);
+        total += nodes;
+    }
+    println!("total: {}", total);
+}
+
+/// The number of leaf nodes in the game tree `depth` plies below `game`.
+/// A pass counts as a ply, and a finished game is a leaf however deep it is.
+fn perft(game: &mut Othello, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = game.legal_moves();
+    if moves.is_empty() {
+        return 1;
+    }
+
+    // The last ply doesn't need to be played out to be counted
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for action in moves {
+        let mv = game.make_move(action);
+        nodes += perft(game, depth - 1);
+        game.unmake_move(mv);
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::othello::Board;
+
+    #[test]
+    fn opening_counts() {
+        let mut game = Othello::new(Board::default());
+        let expected = [4, 12, 56, 244, 1396, 8200, 55092, 390216];
+        for (depth, nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&mut game, depth + 1), *nodes);
+        }
+    }
+}
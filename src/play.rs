@@ -2,7 +2,12 @@ use clap::ArgMatches;
 
 use std::io::{self, Write};
 
-use crate::{othello, solve::*};
+use crate::{
+    common::{extract_algorithm_depth, extract_game},
+    error::ApolloError,
+    othello,
+    solve::*,
+};
 use crossterm::{
     cursor,
     event::{self, Event, KeyCode, KeyEvent},
@@ -11,24 +16,36 @@ use crossterm::{
     terminal::{self, ClearType},
     Result,
 };
-use othello::{Board, Disc, Othello, Position};
+use othello::{Action, Disc, Move, Othello, Position};
+
+const DEFAULT_ALGORITHM: &str = "ab-order:weight-mobility:4";
+
+/// With this few empty squares left on the player's turn, the game is
+/// solved for a win, draw or loss to tell them if they have already lost.
+const LOST_EMPTIES: usize = 16;
 
 struct OthelloPlayer {
     w: io::Stdout,
     position: Position,
     game: Othello,
+    moves: Vec<Move>,
     player: Disc,
     solve: Box<dyn Search>,
+    depth: usize,
+    wld: Box<dyn Search>,
 }
 
 impl OthelloPlayer {
-    pub fn new(board: Board) -> Self {
+    pub fn new(game: Othello, solve: Box<dyn Search>, depth: usize) -> Self {
         OthelloPlayer {
             w: io::stdout(),
             position: Position::new(0, 0),
-            game: Othello::new(board),
+            game,
+            moves: Vec::new(),
             player: Disc::Black,
-            solve: AlphaBetaOrdering::new(HWeightedMobility::new()),
+            solve,
+            depth,
+            wld: EndgameSolver::wld(),
         }
     }
 
@@ -67,8 +84,12 @@ impl OthelloPlayer {
         terminal::disable_raw_mode()
     }
 
-    fn disc_color(&self, disc: Disc) -> Color {
-        match disc {
+    fn square_color(&self, pos: Position) -> Color {
+        if self.game.board().is_blocked(pos) {
+            return Color::DarkGrey;
+        }
+
+        match self.game.board().at(pos) {
             Disc::Black => Color::Black,
             Disc::White => Color::White,
             Disc::Empty => Color::DarkGreen,
@@ -76,8 +97,7 @@ impl OthelloPlayer {
     }
 
     fn move_cursor(&mut self, row: isize, col: isize) -> Result<()> {
-        let disc = self.game.board().at(self.position);
-        self.write_color(self.position, self.disc_color(disc))?;
+        self.write_color(self.position, self.square_color(self.position))?;
 
         self.position.row = row;
         self.position.col = col;
@@ -91,10 +111,7 @@ impl OthelloPlayer {
 
         self.write_color(self.position, color)?;
 
-        self.set_status(format!(
-            "Coordinates: ({}, {})",
-            self.position.row, self.position.col
-        ))
+        self.set_status(format!("Coordinates: {}", self.position))
     }
 
     fn cursor_position(&self, position: Position) -> (u16, u16) {
@@ -129,44 +146,107 @@ impl OthelloPlayer {
         for row in 0..self.game.board().height() {
             for col in 0..self.game.board().width() {
                 let pos = Position::new(row as isize, col as isize);
-                let disc = self.game.board().at(pos);
-                self.write_color(pos, self.disc_color(disc))?;
+                self.write_color(pos, self.square_color(pos))?;
             }
         }
         Ok(())
     }
 
     fn attempt_place(&mut self) -> Result<()> {
-        if self.game.is_valid_move(self.position, self.player) {
-            self.game.place(self.position, self.player);
+        if self.game.to_move() == self.player && self.game.is_valid_move(self.position, self.player)
+        {
+            self.moves
+                .push(self.game.make_move(Action::Place(self.position)));
             self.update_board()?;
             self.move_cursor(0, 0)?;
-            // Do the AI's moves
-            while {
-                self.other_move()?;
-                
-                self.game.valid_moves(self.player).len() == 0
-            } {}
+            self.respond()?;
         } else {
             self.set_status("Invalid position!".into())?;
         }
         Ok(())
     }
 
-    fn other_move(&mut self) -> Result<()> {
-        let opposite = self.player.opponent();
-        let (mv, _) = self.solve.search(&self.game, opposite, 4);
-        if let Some(pos) = mv {
-            self.game.place(pos, opposite);
-        } else {
-            self.set_status(String::from("No valid moves for computer"))?;
+    fn respond(&mut self) -> Result<()> {
+        // Do the AI's moves, passing for the player whenever they are stuck
+        while !self.game.is_game_over() {
+            if self.game.to_move() != self.player {
+                self.other_move()?;
+            } else if self.game.is_legal(Action::Pass) {
+                self.moves.push(self.game.make_move(Action::Pass));
+                self.set_status(String::from("No valid moves, passing"))?;
+            } else {
+                break;
+            }
+        }
+
+        if self.game.is_game_over() {
+            self.set_status(format!("Game over! {}", self.game.result()))?;
         }
+        self.update_outlook()
+    }
+
+    fn other_move(&mut self) -> Result<()> {
+        let mv = match self.solve.search(&self.game, self.depth).best {
+            Some(action) => self.game.make_move(action),
+            None => return Ok(()),
+        };
 
         self.update_board()?;
         self.move_cursor(0, 0)?;
+
+        if let Action::Place(pos) = mv.action() {
+            self.set_status(format!(
+                "Opponent played {}, flipping {}",
+                pos,
+                mv.flipped().len()
+            ))?;
+        }
+        self.moves.push(mv);
         Ok(())
     }
 
+    fn undo(&mut self) -> Result<()> {
+        // Moves the computer made before the player's first disc stay on the board
+        let player = self.player;
+        let placed = |mv: &Move| mv.player() == player && mv.action() != Action::Pass;
+        if !self.moves.iter().any(placed) {
+            return self.set_status("Nothing to undo!".into());
+        }
+
+        // Take back the computer's replies along with the player's last placed disc
+        while let Some(mv) = self.moves.pop() {
+            let last = placed(&mv);
+            self.game.unmake_move(mv);
+            if last {
+                break;
+            }
+        }
+
+        self.update_board()?;
+        self.update_outlook()?;
+        self.move_cursor(self.position.row, self.position.col)
+    }
+
+    /// Warns the player below the status line once every move loses
+    /// against perfect play.
+    fn update_outlook(&mut self) -> Result<()> {
+        let lost = self.game.to_move() == self.player
+            && !self.game.is_game_over()
+            && self.game.empties() <= LOST_EMPTIES
+            && self.wld.search(&self.game, 0).score < 0;
+
+        let outlook_line = 3 + 2 * self.game.board().height();
+        execute!(
+            self.w,
+            cursor::MoveTo(0, outlook_line as u16),
+            style::ResetColor,
+            terminal::Clear(terminal::ClearType::CurrentLine),
+            style::SetBackgroundColor(Color::Black),
+            style::SetForegroundColor(Color::Red),
+            style::Print(if lost { "You are lost!" } else { "" })
+        )
+    }
+
     pub fn looping(&mut self) -> Result<()> {
         loop {
             match read_char()? {
@@ -175,6 +255,7 @@ impl OthelloPlayer {
                 's' => self.move_cursor(self.position.row + 1, self.position.col)?,
                 'd' => self.move_cursor(self.position.row, self.position.col + 1)?,
                 ' ' => self.attempt_place()?,
+                'u' => self.undo()?,
                 'q' => break,
                 _ => (),
             }
@@ -196,9 +277,23 @@ impl OthelloPlayer {
     }
 }
 
-pub fn main(_: &ArgMatches) -> Result<()> {
-    let mut player = OthelloPlayer::new(Board::default());
+pub fn main(matches: &ArgMatches) -> std::result::Result<(), ApolloError> {
+    let game = extract_game(
+        matches.value_of("position"),
+        matches.value_of("size"),
+        matches.value_of("rules"),
+        matches.value_of("blocked"),
+    )?;
+    let (solve, depth) =
+        extract_algorithm_depth(matches.value_of("algorithm").unwrap_or(DEFAULT_ALGORITHM))?;
+
+    run(game, solve, depth).map_err(|e| ApolloError::Io(io::Error::other(e.to_string())))
+}
+
+fn run(game: Othello, solve: Box<dyn Search>, depth: usize) -> Result<()> {
+    let mut player = OthelloPlayer::new(game, solve, depth);
     player.init()?;
+    player.respond()?;
     player.looping()?;
     player.clean()?;
     Ok(())
@@ -1,10 +1,7 @@
-use crate::{
-    common::*,
-    othello::{Board, Othello},
-};
+use crate::{common::*, error::ApolloError, othello::GgfGame};
 use clap::ArgMatches;
 
-pub fn main(matches: &ArgMatches) -> Result<(), String> {
+pub fn main(matches: &ArgMatches) -> Result<(), ApolloError> {
     let a1_arg = matches.value_of("algorithm1").unwrap();
     let a2_arg = matches.value_of("algorithm2").unwrap();
 
@@ -14,11 +11,28 @@ pub fn main(matches: &ArgMatches) -> Result<(), String> {
     let (mut a1, d1) = extract_algorithm_depth(a1_arg)?;
     let (mut a2, d2) = extract_algorithm_depth(a2_arg)?;
 
-    let mut game = Othello::new(Board::default());
+    let start = extract_game(
+        matches.value_of("position"),
+        matches.value_of("size"),
+        matches.value_of("rules"),
+        matches.value_of("blocked"),
+    )?;
+    if matches.value_of("ggf").is_some() {
+        // Fail before playing any games that couldn't be recorded
+        GgfGame::new(&start, Vec::new())?;
+    }
+    let mut game = start.clone();
+    game.record_history();
 
-    play(&mut game, &mut a1, d1, &mut a2, d2);
+    let moves = play(&mut game, &mut a1, d1, &mut a2, d2);
 
     println!("{}", game.board());
+    println!("{}", game.result());
+    println!("{}", game.transcript().unwrap_or_default());
+
+    if let Some(path) = matches.value_of("ggf") {
+        append_ggf(path, &[record_game(&start, moves, a1_arg, a2_arg)?])?;
+    }
 
     Ok(())
 }
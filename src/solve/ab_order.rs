@@ -1,82 +1,93 @@
-use super::{Evaluation, Heuristic, Node, Search};
+use super::{
+    promote, search_tree, search_tree_window, Deadline, Evaluation, Heuristic, Line, Node, Probe,
+    Search, SearchResult, TranspositionTable, Tree, TreeSearch,
+};
 use crate::othello::*;
+use std::time::Instant;
 
 pub struct AlphaBetaOrdering {
     heuristic: Box<dyn Heuristic>,
-    expanded: usize,
-    generated: usize,
+    tree: Tree,
 }
 
 impl AlphaBetaOrdering {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(heuristic: Box<dyn Heuristic>) -> Box<dyn Search> {
+        AlphaBetaOrdering::with_options(heuristic, None, None)
+    }
+
+    /// Searches with a transposition table, so that positions reached more
+    /// than once are only searched once, and with an aspiration window of
+    /// `aspiration` either side of the value found by the last search.
+    pub fn with_options(
+        heuristic: Box<dyn Heuristic>,
+        table: Option<TranspositionTable>,
+        aspiration: Option<Evaluation>,
+    ) -> Box<dyn Search> {
         Box::new(AlphaBetaOrdering {
             heuristic,
-            expanded: 0,
-            generated: 0,
+            tree: Tree::new(table, aspiration),
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn ab_order(
         &mut self,
         node: Node,
-        game: &Othello,
+        game: &mut Othello,
         player: Disc,
-        next_move: Disc,
         depth: usize,
         a: Evaluation,
         b: Evaluation,
         this_eval: Evaluation,
-    ) -> (Option<Position>, Evaluation) {
-        self.expanded += 1;
+    ) -> (Line, Evaluation) {
+        self.tree.expanded += 1;
+
+        if self.tree.deadline.expired(self.tree.expanded) {
+            return (Line::new(), 0);
+        }
 
         if depth == 0 {
-            return (None, this_eval);
+            self.tree.horizon = true;
+            return (Line::new(), this_eval);
         }
 
+        // A position searched before may already have a value, or at least a best move
+        let first_move = match self.tree.probe(game, player, depth, a, b) {
+            Probe::Cutoff(line, value) => return (line, value),
+            Probe::First(first_move) => first_move,
+        };
+
         // What possible moves can we make?
-        let moves = self.successors(node, game, player, next_move);
-
-        // No possible moves from here, see what moves are in our opponent's future
-        if moves.len() == 0 {
-            let (_, value) = self.ab_order(
-                node.opposite(),
-                game,
-                player,
-                next_move.opponent(),
-                depth - 1,
-                a,
-                b,
-                this_eval,
-            );
-            return (None, value);
+        let mut moves = ordered_successors(self.heuristic.as_mut(), node, game, player);
+
+        // No possible moves for either player, the game is over
+        if moves.is_empty() {
+            return (Line::new(), this_eval);
         }
 
-        self.generated += moves.len();
+        promote(&mut moves, first_move, |(mv, _)| *mv);
+        self.tree.generated += moves.len();
 
         // Now, maximize our benefit of each next move
-        match node {
+        let (line, value) = match node {
             Node::Max => {
                 // Max node -> find the best possible move
-                let mut best_move = None;
-                let mut best_value = std::isize::MIN;
+                let mut best_line = Line::new();
+                let mut best_value = isize::MIN;
                 let mut alpha = a;
 
-                for (node_move, node_result, node_eval) in moves.iter() {
+                for (node_move, node_eval) in moves.iter() {
                     // Recursively call minimax to find the maximum value we can force
-                    let (_, value) = self.ab_order(
-                        Node::Min,
-                        node_result,
-                        player,
-                        next_move.opponent(),
-                        depth - 1,
-                        alpha,
-                        b,
-                        *node_eval,
-                    );
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) =
+                        self.ab_order(Node::Min, game, player, depth - 1, alpha, b, *node_eval);
+                    game.unmake_move(mv);
 
                     // Update local maximum
                     if value > best_value {
-                        best_move = Some(*node_move);
+                        line.push(*node_move);
+                        best_line = line;
                         best_value = value;
                     }
 
@@ -89,30 +100,25 @@ impl AlphaBetaOrdering {
                     }
                 }
 
-                (best_move, best_value)
+                (best_line, best_value)
             }
             Node::Min => {
                 // Min node -> assume opponent will choose the worst possible move for us
-                let mut worst_move = None;
-                let mut worst_value = std::isize::MAX;
+                let mut worst_line = Line::new();
+                let mut worst_value = isize::MAX;
                 let mut beta = b;
 
-                for (node_move, node_game, node_eval) in moves.iter() {
+                for (node_move, node_eval) in moves.iter() {
                     // Recursively call minimax to find the minimum value they can force
-                    let (_, value) = self.ab_order(
-                        Node::Max,
-                        node_game,
-                        player,
-                        next_move.opponent(),
-                        depth - 1,
-                        a,
-                        beta,
-                        *node_eval,
-                    );
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) =
+                        self.ab_order(Node::Max, game, player, depth - 1, a, beta, *node_eval);
+                    game.unmake_move(mv);
 
                     // Update local minimum
                     if value < worst_value {
-                        worst_move = Some(*node_move);
+                        line.push(*node_move);
+                        worst_line = line;
                         worst_value = value;
                     }
 
@@ -126,63 +132,81 @@ impl AlphaBetaOrdering {
                     }
                 }
 
-                (worst_move, worst_value)
+                (worst_line, worst_value)
             }
-        }
+        };
+
+        self.tree.store(game, player, depth, a, b, &line, value);
+
+        (line, value)
+    }
+}
+
+/// Every move for the player to move, each with the heuristic value of the
+/// position it leads to, ordered from best to worst for the side at `node`.
+pub(super) fn ordered_successors(
+    heuristic: &mut dyn Heuristic,
+    node: Node,
+    game: &mut Othello,
+    player: Disc,
+) -> Vec<(Action, Evaluation)> {
+    let mut moves: Vec<(Action, Evaluation)> = game
+        .legal_moves()
+        .iter()
+        .map(|m| {
+            let mv = game.make_move(*m);
+            let eval = heuristic.eval(game, player);
+            game.unmake_move(mv);
+            (*m, eval)
+        })
+        .collect();
+
+    match node {
+        Node::Max => moves.sort_by(|(_, e1), (_, e2)| e2.cmp(e1)),
+        Node::Min => moves.sort_by_key(|(_, e)| *e),
     }
 
-    fn successors(
+    moves
+}
+
+impl TreeSearch for AlphaBetaOrdering {
+    fn tree(&mut self) -> &mut Tree {
+        &mut self.tree
+    }
+
+    fn root(
         &mut self,
-        node: Node,
-        game: &Othello,
+        game: &mut Othello,
         player: Disc,
-        next_move: Disc,
-    ) -> Vec<(Position, Othello, Evaluation)> {
-        let mut moves: Vec<(Position, Othello, Evaluation)> = game
-            .valid_moves(next_move)
-            .iter()
-            .map(|m| {
-                let mut successor = game.clone();
-                successor.place(*m, next_move);
-                let eval = self.heuristic.eval(&successor, player);
-                (*m, successor, eval)
-            })
-            .collect();
-
-        match node {
-            Node::Max => moves.sort_by(|(_, _, e1), (_, _, e2)| e2.cmp(&e1)),
-            Node::Min => moves.sort_by(|(_, _, e1), (_, _, e2)| e1.cmp(&e2)),
-        }
-
-        moves
+        depth: usize,
+        a: Evaluation,
+        b: Evaluation,
+    ) -> (Line, Evaluation) {
+        let eval = self.heuristic.eval(game, player);
+        self.ab_order(Node::Max, game, player, depth, a, b, eval)
     }
 }
 
 impl Search for AlphaBetaOrdering {
-    fn search(
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult {
+        search_tree(self, game, depth)
+    }
+
+    fn search_window(
         &mut self,
         game: &Othello,
-        player: Disc,
         depth: usize,
-    ) -> (Option<Position>, Evaluation) {
-        self.expanded = 0;
-        self.ab_order(
-            Node::Max,
-            game,
-            player,
-            player,
-            depth,
-            std::isize::MIN,
-            std::isize::MAX,
-            0,
-        )
+        a: Evaluation,
+        b: Evaluation,
+    ) -> SearchResult {
+        search_tree_window(self, game, depth, a, b)
     }
 
-    fn nodes_expanded(&self) -> usize {
-        self.expanded
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.tree.deadline = Deadline::new(deadline);
     }
 
-    fn nodes_generated(&self) -> usize {
-        self.generated
+    fn set_first_move(&mut self, action: Option<Action>) {
+        self.tree.first_move = action;
     }
 }
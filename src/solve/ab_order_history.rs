@@ -0,0 +1,236 @@
+use super::{promote, Deadline, Evaluation, Heuristic, Line, Node, Search, SearchResult};
+use crate::othello::*;
+use std::time::Instant;
+
+/// Alpha-beta pruning with move ordering that doesn't evaluate any
+/// successors. Moves that caused a cutoff at the same ply (killer moves)
+/// are searched first, then the rest by how often their square has caused
+/// a cutoff anywhere in the tree (the history heuristic).
+pub struct AlphaBetaOrderingHistory {
+    heuristic: Box<dyn Heuristic>,
+    expanded: usize,
+    generated: usize,
+    deadline: Deadline,
+    horizon: bool,
+    first_move: Option<Action>,
+    killers: Vec<[Option<Action>; 2]>,
+    history: Vec<usize>,
+    width: usize,
+}
+
+impl AlphaBetaOrderingHistory {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(heuristic: Box<dyn Heuristic>) -> Box<dyn Search> {
+        Box::new(AlphaBetaOrderingHistory {
+            heuristic,
+            expanded: 0,
+            generated: 0,
+            deadline: Deadline::default(),
+            horizon: false,
+            first_move: None,
+            killers: Vec::new(),
+            history: Vec::new(),
+            width: 0,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn alphabeta(
+        &mut self,
+        node: Node,
+        game: &mut Othello,
+        player: Disc,
+        depth: usize,
+        ply: usize,
+        a: Evaluation,
+        b: Evaluation,
+    ) -> (Line, Evaluation) {
+        self.expanded += 1;
+
+        if self.deadline.expired(self.expanded) {
+            return (Line::new(), 0);
+        }
+
+        if depth == 0 {
+            self.horizon = true;
+            return (Line::new(), self.heuristic.eval(game, player));
+        }
+
+        // What possible moves can we make?
+        let mut moves = self.successors(game, ply);
+
+        // No possible moves for either player, the game is over
+        if moves.is_empty() {
+            return (Line::new(), self.heuristic.eval(game, player));
+        }
+
+        promote(&mut moves, self.first_move.take(), |mv| *mv);
+        self.generated += moves.len();
+
+        // Now, maximize our benefit of each next move
+        match node {
+            Node::Max => {
+                // Max node -> find the best possible move
+                let mut best_line = Line::new();
+                let mut best_value = isize::MIN;
+                let mut alpha = a;
+
+                for node_move in moves.iter() {
+                    // Recursively call minimax to find the maximum value we can force
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) =
+                        self.alphabeta(Node::Min, game, player, depth - 1, ply + 1, alpha, b);
+                    game.unmake_move(mv);
+
+                    // Update local maximum
+                    if value > best_value {
+                        line.push(*node_move);
+                        best_line = line;
+                        best_value = value;
+                    }
+
+                    if best_value > alpha {
+                        alpha = best_value;
+                    }
+
+                    if alpha >= b {
+                        self.reward(*node_move, depth, ply);
+                        break;
+                    }
+                }
+
+                (best_line, best_value)
+            }
+            Node::Min => {
+                // Min node -> assume opponent will choose the worst possible move for us
+                let mut worst_line = Line::new();
+                let mut worst_value = isize::MAX;
+                let mut beta = b;
+
+                for node_move in moves.iter() {
+                    // Recursively call minimax to find the minimum value they can force
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) =
+                        self.alphabeta(Node::Max, game, player, depth - 1, ply + 1, a, beta);
+                    game.unmake_move(mv);
+
+                    // Update local minimum
+                    if value < worst_value {
+                        line.push(*node_move);
+                        worst_line = line;
+                        worst_value = value;
+                    }
+
+                    // Update local beta value
+                    if worst_value < b {
+                        beta = worst_value;
+                    }
+
+                    if a >= beta {
+                        self.reward(*node_move, depth, ply);
+                        break;
+                    }
+                }
+
+                (worst_line, worst_value)
+            }
+        }
+    }
+
+    /// The legal moves in `game`, killer moves at `ply` first,
+    /// then by their history score.
+    fn successors(&self, game: &Othello, ply: usize) -> Vec<Action> {
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        let mut moves: Vec<(Action, usize)> = game
+            .legal_moves()
+            .into_iter()
+            .map(|m| {
+                let score = if Some(m) == killers[0] {
+                    usize::MAX
+                } else if Some(m) == killers[1] {
+                    usize::MAX - 1
+                } else {
+                    self.square(m).map_or(0, |square| self.history[square])
+                };
+                (m, score)
+            })
+            .collect();
+
+        moves.sort_by(|(_, s1), (_, s2)| s2.cmp(s1));
+        moves.into_iter().map(|(m, _)| m).collect()
+    }
+
+    /// Remembers `action` as having caused a cutoff `depth` from the bottom
+    /// of the tree. Cutoffs higher up prune more, so they count for more.
+    fn reward(&mut self, action: Action, depth: usize, ply: usize) {
+        if let Some(square) = self.square(action) {
+            self.history[square] += depth * depth;
+        }
+
+        if ply >= self.killers.len() {
+            self.killers.resize(ply + 1, [None, None]);
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(action) {
+            killers[1] = killers[0];
+            killers[0] = Some(action);
+        }
+    }
+
+    fn square(&self, action: Action) -> Option<usize> {
+        match action {
+            Action::Place(pos) => Some(pos.row as usize * self.width + pos.col as usize),
+            Action::Pass => None,
+        }
+    }
+}
+
+impl Search for AlphaBetaOrderingHistory {
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult {
+        let start = Instant::now();
+        self.expanded = 0;
+        self.generated = 0;
+        self.horizon = false;
+
+        // Old cutoffs say less about this position than new ones
+        let board = game.board();
+        let squares = board.width() * board.height();
+        if self.width != board.width() || self.history.len() != squares {
+            self.width = board.width();
+            self.history = vec![0; squares];
+            self.killers.clear();
+        } else {
+            self.history.iter_mut().for_each(|score| *score /= 2);
+        }
+
+        let mut game = game.clone();
+        let player = game.to_move();
+        let (line, score) = self.alphabeta(
+            Node::Max,
+            &mut game,
+            player,
+            depth,
+            0,
+            isize::MIN,
+            isize::MAX,
+        );
+        SearchResult::new(
+            line,
+            score,
+            depth,
+            self.expanded,
+            self.generated,
+            start,
+            self.horizon,
+            self.deadline.passed(),
+        )
+    }
+
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = Deadline::new(deadline);
+    }
+
+    fn set_first_move(&mut self, action: Option<Action>) {
+        self.first_move = action;
+    }
+}
@@ -1,5 +1,6 @@
-use super::{Evaluation, Heuristic, Node, Search};
+use super::{promote, Deadline, Evaluation, Heuristic, Line, Node, Search, SearchResult};
 use crate::othello::*;
+use std::time::Instant;
 
 const BOARD_STEP: usize = 2;
 
@@ -7,75 +8,74 @@ pub struct AlphaBetaOrderingUnit {
     heuristic: Box<dyn Heuristic>,
     expanded: usize,
     generated: usize,
+    deadline: Deadline,
+    horizon: bool,
+    first_move: Option<Action>,
 }
 
 impl AlphaBetaOrderingUnit {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(heuristic: Box<dyn Heuristic>) -> Box<dyn Search> {
         Box::new(AlphaBetaOrderingUnit {
             heuristic,
             expanded: 0,
             generated: 0,
+            deadline: Deadline::default(),
+            horizon: false,
+            first_move: None,
         })
     }
 
     fn alphabeta(
         &mut self,
         node: Node,
-        game: &Othello,
+        game: &mut Othello,
         player: Disc,
-        next_move: Disc,
         depth: usize,
         a: Evaluation,
         b: Evaluation,
-    ) -> (Option<Position>, Evaluation) {
+    ) -> (Line, Evaluation) {
         self.expanded += 1;
 
+        if self.deadline.expired(self.expanded) {
+            return (Line::new(), 0);
+        }
+
         if depth == 0 {
-            return (None, self.heuristic.eval(game, player));
+            self.horizon = true;
+            return (Line::new(), self.heuristic.eval(game, player));
         }
 
         // What possible moves can we make?
-        let moves = self.successors(game, next_move, player, node);
-
-        // No possible moves from here, see what moves are in our opponent's future
-        if moves.len() == 0 {
-            let (_, value) = self.alphabeta(
-                node.opposite(),
-                game,
-                player,
-                next_move.opponent(),
-                depth - 1,
-                a,
-                b,
-            );
-            return (None, value);
+        let mut moves = self.successors(game, player, node);
+
+        // No possible moves for either player, the game is over
+        if moves.is_empty() {
+            return (Line::new(), self.heuristic.eval(game, player));
         }
 
+        promote(&mut moves, self.first_move.take(), |mv| *mv);
         self.generated += moves.len();
 
         // Now, maximize our benefit of each next move
         match node {
             Node::Max => {
                 // Max node -> find the best possible move
-                let mut best_move = None;
-                let mut best_value = std::isize::MIN;
+                let mut best_line = Line::new();
+                let mut best_value = isize::MIN;
                 let mut alpha = a;
 
-                for (node_move, node_game) in moves.iter() {
+                for node_move in moves.iter() {
                     // Recursively call minimax to find the maximum value we can force
-                    let (_, value) = self.alphabeta(
-                        Node::Min,
-                        node_game,
-                        player,
-                        next_move.opponent(),
-                        depth - 1,
-                        alpha,
-                        b,
-                    );
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) =
+                        self.alphabeta(Node::Min, game, player, depth - 1, alpha, b);
+                    game.unmake_move(mv);
 
                     // Update local maximum
                     if value > best_value {
-                        best_move = Some(*node_move);
+                        line.push(*node_move);
+                        best_line = line;
                         best_value = value;
                     }
 
@@ -88,29 +88,25 @@ impl AlphaBetaOrderingUnit {
                     }
                 }
 
-                (best_move, best_value)
+                (best_line, best_value)
             }
             Node::Min => {
                 // Min node -> assume opponent will choose the worst possible move for us
-                let mut worst_move = None;
-                let mut worst_value = std::isize::MAX;
+                let mut worst_line = Line::new();
+                let mut worst_value = isize::MAX;
                 let mut beta = b;
 
-                for (node_move, node_game) in moves.iter() {
+                for node_move in moves.iter() {
                     // Recursively call minimax to find the minimum value they can force
-                    let (_, value) = self.alphabeta(
-                        Node::Max,
-                        node_game,
-                        player,
-                        next_move.opponent(),
-                        depth - 1,
-                        a,
-                        beta,
-                    );
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) =
+                        self.alphabeta(Node::Max, game, player, depth - 1, a, beta);
+                    game.unmake_move(mv);
 
                     // Update local minimum
                     if value < worst_value {
-                        worst_move = Some(*node_move);
+                        line.push(*node_move);
+                        worst_line = line;
                         worst_value = value;
                     }
 
@@ -124,28 +120,29 @@ impl AlphaBetaOrderingUnit {
                     }
                 }
 
-                (worst_move, worst_value)
+                (worst_line, worst_value)
             }
         }
     }
 
-    fn successors(&self, game: &Othello, next_move: Disc, player: Disc, node: Node) -> Vec<(Position, Othello)> {
-        let mut moves: Vec<(Position, Othello, Evaluation)> = game.valid_moves(next_move)
+    fn successors(&self, game: &mut Othello, player: Disc, node: Node) -> Vec<Action> {
+        let mut moves: Vec<(Action, Evaluation)> = game
+            .legal_moves()
             .iter()
             .map(|m| {
-                let mut successor = game.clone();
-                successor.place(*m, next_move);
+                let mv = game.make_move(*m);
                 let eval = self.ordering_function(game, player);
-                (*m, successor, eval)
+                game.unmake_move(mv);
+                (*m, eval)
             })
             .collect();
 
         match node {
-            Node::Max => moves.sort_by(|(_, _, e1), (_, _, e2)| e2.cmp(&e1)),
-            Node::Min => moves.sort_by(|(_, _, e1), (_, _, e2)| e1.cmp(&e2)),
+            Node::Max => moves.sort_by(|(_, e1), (_, e2)| e2.cmp(e1)),
+            Node::Min => moves.sort_by_key(|(_, e)| *e),
         }
-    
-        moves.into_iter().map(|(p, g, _)| (p, g)).collect()
+
+        moves.into_iter().map(|(m, _)| m).collect()
     }
 
     fn ordering_function(&self, game: &Othello, player: Disc) -> Evaluation {
@@ -160,7 +157,7 @@ impl AlphaBetaOrderingUnit {
                     eval += 1;
                 } else if disc == opponent {
                     eval -= 1;
-                }   
+                }
             }
         }
 
@@ -169,29 +166,32 @@ impl AlphaBetaOrderingUnit {
 }
 
 impl Search for AlphaBetaOrderingUnit {
-    fn search(
-        &mut self,
-        game: &Othello,
-        player: Disc,
-        depth: usize,
-    ) -> (Option<Position>, Evaluation) {
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult {
+        let start = Instant::now();
         self.expanded = 0;
-        self.alphabeta(
-            Node::Max,
-            game,
-            player,
-            player,
+        self.generated = 0;
+        self.horizon = false;
+        let mut game = game.clone();
+        let player = game.to_move();
+        let (line, score) =
+            self.alphabeta(Node::Max, &mut game, player, depth, isize::MIN, isize::MAX);
+        SearchResult::new(
+            line,
+            score,
             depth,
-            std::isize::MIN,
-            std::isize::MAX,
+            self.expanded,
+            self.generated,
+            start,
+            self.horizon,
+            self.deadline.passed(),
         )
     }
 
-    fn nodes_expanded(&self) -> usize {
-        self.expanded
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = Deadline::new(deadline);
     }
 
-    fn nodes_generated(&self) -> usize {
-        self.generated
+    fn set_first_move(&mut self, action: Option<Action>) {
+        self.first_move = action;
     }
 }
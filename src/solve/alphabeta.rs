@@ -1,79 +1,91 @@
-use super::{Evaluation, Heuristic, Node, Search};
+use super::{
+    promote, search_tree, search_tree_window, Deadline, Evaluation, Heuristic, Line, Node, Probe,
+    Search, SearchResult, TranspositionTable, Tree, TreeSearch,
+};
 use crate::othello::*;
+use std::time::Instant;
 
 pub struct AlphaBeta {
     heuristic: Box<dyn Heuristic>,
-    expanded: usize,
-    generated: usize,
+    tree: Tree,
 }
 
 impl AlphaBeta {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(heuristic: Box<dyn Heuristic>) -> Box<dyn Search> {
+        AlphaBeta::with_options(heuristic, None, None)
+    }
+
+    /// Searches with a transposition table, so that positions reached more
+    /// than once are only searched once, and with an aspiration window of
+    /// `aspiration` either side of the value found by the last search.
+    pub fn with_options(
+        heuristic: Box<dyn Heuristic>,
+        table: Option<TranspositionTable>,
+        aspiration: Option<Evaluation>,
+    ) -> Box<dyn Search> {
         Box::new(AlphaBeta {
             heuristic,
-            expanded: 0,
-            generated: 0,
+            tree: Tree::new(table, aspiration),
         })
     }
 
     fn alphabeta(
         &mut self,
         node: Node,
-        game: &Othello,
+        game: &mut Othello,
         player: Disc,
-        next_move: Disc,
         depth: usize,
         a: Evaluation,
         b: Evaluation,
-    ) -> (Option<Position>, Evaluation) {
-        self.expanded += 1;
+    ) -> (Line, Evaluation) {
+        self.tree.expanded += 1;
+
+        if self.tree.deadline.expired(self.tree.expanded) {
+            return (Line::new(), 0);
+        }
 
         if depth == 0 {
-            return (None, self.heuristic.eval(game, player));
+            self.tree.horizon = true;
+            return (Line::new(), self.heuristic.eval(game, player));
         }
 
+        // A position searched before may already have a value, or at least a best move
+        let first_move = match self.tree.probe(game, player, depth, a, b) {
+            Probe::Cutoff(line, value) => return (line, value),
+            Probe::First(first_move) => first_move,
+        };
+
         // What possible moves can we make?
-        let moves = self.successors(game, next_move);
-
-        // No possible moves from here, see what moves are in our opponent's future
-        if moves.len() == 0 {
-            let (_, value) = self.alphabeta(
-                node.opposite(),
-                game,
-                player,
-                next_move.opponent(),
-                depth - 1,
-                a,
-                b,
-            );
-            return (None, value);
+        let mut moves = game.legal_moves();
+
+        // No possible moves for either player, the game is over
+        if moves.is_empty() {
+            return (Line::new(), self.heuristic.eval(game, player));
         }
 
-        self.generated += moves.len();
+        promote(&mut moves, first_move, |mv| *mv);
+        self.tree.generated += moves.len();
 
         // Now, maximize our benefit of each next move
-        match node {
+        let (line, value) = match node {
             Node::Max => {
                 // Max node -> find the best possible move
-                let mut best_move = None;
-                let mut best_value = std::isize::MIN;
+                let mut best_line = Line::new();
+                let mut best_value = isize::MIN;
                 let mut alpha = a;
 
-                for (node_move, node_game) in moves.iter() {
+                for node_move in moves.iter() {
                     // Recursively call minimax to find the maximum value we can force
-                    let (_, value) = self.alphabeta(
-                        Node::Min,
-                        node_game,
-                        player,
-                        next_move.opponent(),
-                        depth - 1,
-                        alpha,
-                        b,
-                    );
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) =
+                        self.alphabeta(Node::Min, game, player, depth - 1, alpha, b);
+                    game.unmake_move(mv);
 
                     // Update local maximum
                     if value > best_value {
-                        best_move = Some(*node_move);
+                        line.push(*node_move);
+                        best_line = line;
                         best_value = value;
                     }
 
@@ -86,29 +98,25 @@ impl AlphaBeta {
                     }
                 }
 
-                (best_move, best_value)
+                (best_line, best_value)
             }
             Node::Min => {
                 // Min node -> assume opponent will choose the worst possible move for us
-                let mut worst_move = None;
-                let mut worst_value = std::isize::MAX;
+                let mut worst_line = Line::new();
+                let mut worst_value = isize::MAX;
                 let mut beta = b;
 
-                for (node_move, node_game) in moves.iter() {
+                for node_move in moves.iter() {
                     // Recursively call minimax to find the minimum value they can force
-                    let (_, value) = self.alphabeta(
-                        Node::Max,
-                        node_game,
-                        player,
-                        next_move.opponent(),
-                        depth - 1,
-                        a,
-                        beta,
-                    );
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) =
+                        self.alphabeta(Node::Max, game, player, depth - 1, a, beta);
+                    game.unmake_move(mv);
 
                     // Update local minimum
                     if value < worst_value {
-                        worst_move = Some(*node_move);
+                        line.push(*node_move);
+                        worst_line = line;
                         worst_value = value;
                     }
 
@@ -122,47 +130,53 @@ impl AlphaBeta {
                     }
                 }
 
-                (worst_move, worst_value)
+                (worst_line, worst_value)
             }
-        }
+        };
+
+        self.tree.store(game, player, depth, a, b, &line, value);
+
+        (line, value)
     }
+}
 
-    fn successors(&self, game: &Othello, next_move: Disc) -> Vec<(Position, Othello)> {
-        game.valid_moves(next_move)
-            .iter()
-            .map(|m| {
-                let mut successor = game.clone();
-                successor.place(*m, next_move);
-                (*m, successor)
-            })
-            .collect()
+impl TreeSearch for AlphaBeta {
+    fn tree(&mut self) -> &mut Tree {
+        &mut self.tree
+    }
+
+    fn root(
+        &mut self,
+        game: &mut Othello,
+        player: Disc,
+        depth: usize,
+        a: Evaluation,
+        b: Evaluation,
+    ) -> (Line, Evaluation) {
+        self.alphabeta(Node::Max, game, player, depth, a, b)
     }
 }
 
 impl Search for AlphaBeta {
-    fn search(
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult {
+        search_tree(self, game, depth)
+    }
+
+    fn search_window(
         &mut self,
         game: &Othello,
-        player: Disc,
         depth: usize,
-    ) -> (Option<Position>, Evaluation) {
-        self.expanded = 0;
-        self.alphabeta(
-            Node::Max,
-            game,
-            player,
-            player,
-            depth,
-            std::isize::MIN,
-            std::isize::MAX,
-        )
+        a: Evaluation,
+        b: Evaluation,
+    ) -> SearchResult {
+        search_tree_window(self, game, depth, a, b)
     }
 
-    fn nodes_expanded(&self) -> usize {
-        self.expanded
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.tree.deadline = Deadline::new(deadline);
     }
 
-    fn nodes_generated(&self) -> usize {
-        self.generated
+    fn set_first_move(&mut self, action: Option<Action>) {
+        self.tree.first_move = action;
     }
 }
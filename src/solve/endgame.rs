@@ -0,0 +1,634 @@
+use super::{
+    promote, Bound, Deadline, Evaluation, Line, Node, Search, SearchResult, TranspositionTable,
+};
+use crate::othello::*;
+use std::cmp::Reverse;
+use std::time::Instant;
+
+// With this few empty squares left, looking ahead at the opponent's
+// mobility to order moves costs more than it saves
+const LAST_EMPTIES: usize = 6;
+
+// The size of the transposition table for positions with more empty squares
+const TABLE_MEGABYTES: usize = 16;
+
+/// Plays perfectly to the end of the game, whatever the depth. Scores are
+/// the final disc differential. Moves that leave the opponent the fewest
+/// replies are searched first (fastest-first), then moves into regions of
+/// the board with an odd number of empty squares (parity).
+pub struct EndgameSolver {
+    wld: bool,
+    expanded: usize,
+    generated: usize,
+    deadline: Deadline,
+    table: TranspositionTable,
+}
+
+impl EndgameSolver {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> Box<dyn Search> {
+        Box::new(EndgameSolver::with_wld(false))
+    }
+
+    /// Only finds out whether the player to move wins, draws or loses, which
+    /// takes a fraction of the time of finding the exact differential. Scores
+    /// are 1 for a win, 0 for a draw and -1 for a loss.
+    pub fn wld() -> Box<dyn Search> {
+        Box::new(EndgameSolver::with_wld(true))
+    }
+
+    /// Every move for the player to move in `game` that achieves the best
+    /// outcome they can force, and that outcome: 1 for a win, 0 for a draw
+    /// and -1 for a loss. Each move is solved for a win, draw or loss in turn.
+    pub fn wld_moves(game: &Othello) -> (Evaluation, Vec<Action>) {
+        let mut solver = EndgameSolver::with_wld(true);
+        solver.table.new_search();
+
+        let mut game = game.clone();
+        let player = game.to_move();
+        let empties = game.empties();
+        let mut best = game.result().margin(player).signum();
+        let mut moves = Vec::new();
+
+        for action in game.legal_moves() {
+            let mv = game.make_move(action);
+            let left = remaining(action, empties);
+            let (_, value) = solver.solve(Node::Min, &mut game, player, left, -1, 1);
+            game.unmake_move(mv);
+
+            let value = value.signum();
+            if moves.is_empty() || value > best {
+                best = value;
+                moves.clear();
+            }
+            if value == best {
+                moves.push(action);
+            }
+        }
+
+        (best, moves)
+    }
+
+    fn with_wld(wld: bool) -> EndgameSolver {
+        EndgameSolver {
+            wld,
+            expanded: 0,
+            generated: 0,
+            deadline: Deadline::default(),
+            table: TranspositionTable::new(TABLE_MEGABYTES),
+        }
+    }
+
+    fn solve(
+        &mut self,
+        node: Node,
+        game: &mut Othello,
+        player: Disc,
+        empties: usize,
+        a: Evaluation,
+        b: Evaluation,
+    ) -> (Line, Evaluation) {
+        // Near the end, positions are rarely reached twice and cheap to solve again
+        if empties <= LAST_EMPTIES && !center_open(game) {
+            return self.last_empties(node, game, a, b);
+        }
+
+        self.expanded += 1;
+
+        if self.deadline.expired(self.expanded) {
+            return (Line::new(), 0);
+        }
+
+        let mut moves = fastest_first(game);
+
+        // No possible moves for either player, the game is over
+        if moves.is_empty() {
+            return (Line::new(), game.result().margin(player));
+        }
+
+        if let Some(entry) = self.table.probe(game, player) {
+            if entry.cuts_off(empties, a, b) {
+                self.table.cutoff();
+                return (entry.best.into_iter().collect(), entry.score);
+            }
+            promote(&mut moves, entry.best, |mv| *mv);
+        }
+
+        self.generated += moves.len();
+        let (line, value) = self.search_moves(node, game, player, empties, a, b, &moves);
+
+        if !self.deadline.passed() {
+            let bound = Bound::of(value, a, b);
+            self.table
+                .store(game, player, empties, value, bound, line.last().copied());
+        }
+
+        (line, value)
+    }
+
+    /// Searches `moves` in order with alpha-beta pruning.
+    #[allow(clippy::too_many_arguments)]
+    fn search_moves(
+        &mut self,
+        node: Node,
+        game: &mut Othello,
+        player: Disc,
+        empties: usize,
+        a: Evaluation,
+        b: Evaluation,
+        moves: &[Action],
+    ) -> (Line, Evaluation) {
+        match node {
+            Node::Max => {
+                let mut best_line = Line::new();
+                let mut best_value = isize::MIN;
+                let mut alpha = a;
+
+                for node_move in moves.iter() {
+                    let left = remaining(*node_move, empties);
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) = self.solve(Node::Min, game, player, left, alpha, b);
+                    game.unmake_move(mv);
+
+                    if value > best_value {
+                        line.push(*node_move);
+                        best_line = line;
+                        best_value = value;
+                    }
+
+                    if best_value > alpha {
+                        alpha = best_value;
+                    }
+
+                    if alpha >= b {
+                        break;
+                    }
+                }
+
+                (best_line, best_value)
+            }
+            Node::Min => {
+                let mut worst_line = Line::new();
+                let mut worst_value = isize::MAX;
+                let mut beta = b;
+
+                for node_move in moves.iter() {
+                    let left = remaining(*node_move, empties);
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) = self.solve(Node::Max, game, player, left, a, beta);
+                    game.unmake_move(mv);
+
+                    if value < worst_value {
+                        line.push(*node_move);
+                        worst_line = line;
+                        worst_value = value;
+                    }
+
+                    if worst_value < beta {
+                        beta = worst_value;
+                    }
+
+                    if a >= beta {
+                        break;
+                    }
+                }
+
+                (worst_line, worst_value)
+            }
+        }
+    }
+
+    /// Solves the last few empty squares on bare bitboards, without
+    /// making moves on `game`.
+    fn last_empties(
+        &mut self,
+        node: Node,
+        game: &Othello,
+        a: Evaluation,
+        b: Evaluation,
+    ) -> (Line, Evaluation) {
+        let board = game.board();
+        let mover = game.to_move();
+        let own = board.discs(mover);
+        let other = board.discs(mover.opponent());
+
+        // Every score is within the board size, and the window must be negated
+        let bound = board.len() as isize + 1;
+        let (a, b) = (a.clamp(-bound, bound), b.clamp(-bound, bound));
+
+        let mut last = LastEmpties::new(game);
+        let (line, value) = match node {
+            Node::Max => last.solve(own, other, board.empty(), a, b),
+            Node::Min => {
+                let (line, value) = last.solve(own, other, board.empty(), -b, -a);
+                (line, -value)
+            }
+        };
+
+        self.expanded += last.expanded;
+        self.generated += last.generated;
+        (line, value)
+    }
+}
+
+impl Search for EndgameSolver {
+    /// Searches to the end of the game, however deep that is.
+    fn search(&mut self, game: &Othello, _depth: usize) -> SearchResult {
+        let start = Instant::now();
+        self.expanded = 0;
+        self.generated = 0;
+        self.table.new_search();
+
+        let mut game = game.clone();
+        let player = game.to_move();
+        let empties = game.empties();
+
+        // The narrowest window around zero that still tells a win from a draw from a loss
+        let (a, b) = if self.wld {
+            (-1, 1)
+        } else {
+            (isize::MIN, isize::MAX)
+        };
+        let (line, score) = self.solve(Node::Max, &mut game, player, empties, a, b);
+        let score = if self.wld { score.signum() } else { score };
+
+        let mut result = SearchResult::new(
+            line,
+            score,
+            empties,
+            self.expanded,
+            self.generated,
+            start,
+            false,
+            self.deadline.passed(),
+        );
+        result.tt_hits = self.table.hits();
+        result.tt_cutoffs = self.table.cutoffs();
+        result
+    }
+
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = Deadline::new(deadline);
+    }
+}
+
+/// Searches with `search` until there are `empties` or fewer empty squares
+/// left, then solves the rest of the game with `solver`.
+pub struct Endgame {
+    search: Box<dyn Search>,
+    solver: Box<dyn Search>,
+    empties: usize,
+}
+
+impl Endgame {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        search: Box<dyn Search>,
+        solver: Box<dyn Search>,
+        empties: usize,
+    ) -> Box<dyn Search> {
+        Box::new(Endgame {
+            search,
+            solver,
+            empties,
+        })
+    }
+
+    fn active(&mut self, game: &Othello) -> &mut Box<dyn Search> {
+        if game.empties() <= self.empties {
+            &mut self.solver
+        } else {
+            &mut self.search
+        }
+    }
+}
+
+impl Search for Endgame {
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult {
+        self.active(game).search(game, depth)
+    }
+
+    fn search_window(
+        &mut self,
+        game: &Othello,
+        depth: usize,
+        a: Evaluation,
+        b: Evaluation,
+    ) -> SearchResult {
+        self.active(game).search_window(game, depth, a, b)
+    }
+
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.search.set_deadline(deadline);
+        self.solver.set_deadline(deadline);
+    }
+
+    fn set_first_move(&mut self, action: Option<Action>) {
+        self.search.set_first_move(action);
+        self.solver.set_first_move(action);
+    }
+}
+
+/// The number of empty squares left after `action`.
+fn remaining(action: Action, empties: usize) -> usize {
+    match action {
+        Action::Place(_) => empties - 1,
+        Action::Pass => empties,
+    }
+}
+
+/// The legal moves, those leaving the opponent the fewest replies first.
+/// Cutting down the opponent's choices shrinks the tree after every move,
+/// and tends to be good play besides. Ties go to corners, then to moves
+/// that keep our own options open, then by parity.
+fn fastest_first(game: &mut Othello) -> Vec<Action> {
+    let odd = odd_regions(game);
+    let mut moves: Vec<_> = game
+        .legal_moves()
+        .into_iter()
+        .map(|m| {
+            let even = !is_odd(game, odd, m);
+            let corner = is_corner(game.board(), m);
+            let mv = game.make_move(m);
+            let replies = game.mobility(game.to_move());
+            let own = game.mobility(game.to_move().opponent());
+            game.unmake_move(mv);
+
+            (m, (replies, !corner, Reverse(own), even))
+        })
+        .collect();
+
+    moves.sort_by_key(|(_, key)| *key);
+    moves.into_iter().map(|(m, _)| m).collect()
+}
+
+fn is_corner(board: &Board, action: Action) -> bool {
+    let (last_row, last_col) = (board.height() as isize - 1, board.width() as isize - 1);
+    match action {
+        Action::Place(pos) => {
+            (pos.row == 0 || pos.row == last_row) && (pos.col == 0 || pos.col == last_col)
+        }
+        Action::Pass => false,
+    }
+}
+
+/// Whether Reversi rules still require the center to be filled first,
+/// which moves on bare bitboards don't know about.
+fn center_open(game: &Othello) -> bool {
+    let board = game.board();
+    game.rules().fill_center && board.center() & board.empty() != 0
+}
+
+/// One bit for each quadrant of the board with an odd number of empty squares.
+fn odd_regions(game: &Othello) -> u8 {
+    let board = game.board();
+    board
+        .positions(board.empty())
+        .fold(0, |odd, pos| odd ^ 1 << region(board, pos))
+}
+
+fn is_odd(game: &Othello, odd: u8, action: Action) -> bool {
+    match action {
+        Action::Place(pos) => odd & 1 << region(game.board(), pos) != 0,
+        Action::Pass => false,
+    }
+}
+
+fn region(board: &Board, pos: Position) -> usize {
+    let row = pos.row as usize * 2 / board.height();
+    let col = pos.col as usize * 2 / board.width();
+    row * 2 + col
+}
+
+/// A position with only a few empty squares left, searched with negamax
+/// from the point of view of the player to move. Moves into quadrants with
+/// an odd number of empty squares are searched first: the last move into
+/// a region with an odd number of squares left is more often the last move
+/// there.
+struct LastEmpties<'a> {
+    board: &'a Board,
+    quadrants: [Bits; 4],
+    misere: bool,
+    expanded: usize,
+    generated: usize,
+}
+
+impl<'a> LastEmpties<'a> {
+    fn new(game: &'a Othello) -> Self {
+        let board = game.board();
+        let mut quadrants = [0; 4];
+        for index in 0..board.len() {
+            quadrants[region(board, board.position(index))] |= 1 << index;
+        }
+
+        LastEmpties {
+            board,
+            quadrants,
+            misere: game.rules().misere,
+            expanded: 0,
+            generated: 0,
+        }
+    }
+
+    fn solve(
+        &mut self,
+        own: Bits,
+        other: Bits,
+        empty: Bits,
+        a: Evaluation,
+        b: Evaluation,
+    ) -> (Line, Evaluation) {
+        self.expanded += 1;
+
+        if empty & empty.wrapping_sub(1) == 0 {
+            return self.last_empty(own, other, empty);
+        }
+
+        let odd = self
+            .quadrants
+            .iter()
+            .filter(|quadrant| (empty & **quadrant).count_ones() % 2 == 1)
+            .fold(0, |odd, quadrant| odd | quadrant);
+
+        let mut best_line = Line::new();
+        let mut best_value = None;
+        let mut alpha = a;
+
+        for squares in [empty & odd, empty & !odd] {
+            let mut squares = squares;
+            while squares != 0 {
+                let index = squares.trailing_zeros() as usize;
+                let mv = 1 << index;
+                squares &= squares - 1;
+
+                let flips = flips_between(self.board, mv, own, other);
+                if flips == 0 {
+                    continue;
+                }
+
+                self.generated += 1;
+                let (mut line, value) =
+                    self.solve(other & !flips, own | mv | flips, empty & !mv, -b, -alpha);
+                let value = -value;
+
+                if best_value.map_or(true, |best| value > best) {
+                    line.push(Action::Place(self.board.position(index)));
+                    best_line = line;
+                    best_value = Some(value);
+                }
+
+                alpha = alpha.max(value);
+                if alpha >= b {
+                    return (best_line, value);
+                }
+            }
+        }
+
+        if let Some(value) = best_value {
+            return (best_line, value);
+        }
+
+        // We can't move, so pass if the opponent can
+        if self.can_move(other, own, empty) {
+            self.generated += 1;
+            let (mut line, value) = self.solve(other, own, empty, -b, -a);
+            line.push(Action::Pass);
+            return (line, -value);
+        }
+
+        (Line::new(), self.margin(own, other, empty))
+    }
+
+    /// Plays out the last empty square without searching: whoever can
+    /// play there does, the player to move first.
+    fn last_empty(&mut self, own: Bits, other: Bits, empty: Bits) -> (Line, Evaluation) {
+        if empty == 0 {
+            return (Line::new(), self.margin(own, other, empty));
+        }
+
+        let pos = self.board.position(empty.trailing_zeros() as usize);
+        let flips = flips_between(self.board, empty, own, other);
+        if flips != 0 {
+            self.generated += 1;
+            let value = self.margin(own | empty | flips, other & !flips, 0);
+            return (vec![Action::Place(pos)], value);
+        }
+
+        let flips = flips_between(self.board, empty, other, own);
+        if flips != 0 {
+            self.generated += 2;
+            let value = self.margin(own & !flips, other | empty | flips, 0);
+            return (vec![Action::Place(pos), Action::Pass], value);
+        }
+
+        (Line::new(), self.margin(own, other, empty))
+    }
+
+    fn can_move(&self, own: Bits, other: Bits, empty: Bits) -> bool {
+        self.board
+            .positions(empty)
+            .any(|pos| flips_between(self.board, 1 << self.board.index(pos), own, other) != 0)
+    }
+
+    /// The final disc differential for the player to move.
+    fn margin(&self, own: Bits, other: Bits, empty: Bits) -> Evaluation {
+        let result = GameResult {
+            black: own.count_ones() as usize,
+            white: other.count_ones() as usize,
+            empty: empty.count_ones() as usize,
+            misere: self.misere,
+        };
+        result.margin(Disc::Black)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The final disc differential with perfect play, searching every line.
+    fn brute_force(game: &mut Othello, player: Disc) -> Evaluation {
+        let moves = game.legal_moves();
+        if moves.is_empty() {
+            return game.result().margin(player);
+        }
+
+        let max = game.to_move() == player;
+        let values = moves.into_iter().map(|action| {
+            let mv = game.make_move(action);
+            let value = brute_force(game, player);
+            game.unmake_move(mv);
+            value
+        });
+
+        if max {
+            values.max().unwrap()
+        } else {
+            values.min().unwrap()
+        }
+    }
+
+    /// Positions with at most `empties` empty squares left, from games
+    /// played with moves picked by the ply they are played on.
+    fn endgames(start: &Othello, empties: usize) -> Vec<Othello> {
+        (1..12)
+            .filter_map(|seed| {
+                let mut game = start.clone();
+                let mut ply = 0;
+                while game.empties() > empties && !game.is_game_over() {
+                    let moves = game.legal_moves();
+                    game.play(moves[(ply * seed + seed / 2) % moves.len()]);
+                    ply += 1;
+                }
+                Some(game).filter(|game| !game.is_game_over())
+            })
+            .collect()
+    }
+
+    fn starts() -> Vec<Othello> {
+        let misere = Rules {
+            misere: true,
+            ..Rules::OTHELLO
+        };
+        let mut blocked = Board::with_size(6);
+        blocked.block(Position::new(0, 5));
+
+        vec![
+            Othello::new(Board::default()),
+            Othello::with_rules(Board::default(), Disc::Black, misere),
+            Othello::new(blocked),
+        ]
+    }
+
+    #[test]
+    fn solves_exactly() {
+        for start in starts() {
+            for mut game in endgames(&start, 8) {
+                let player = game.to_move();
+                let result = EndgameSolver::new().search(&game, 1);
+                assert_eq!(result.score, brute_force(&mut game, player), "{}", game);
+                assert!(result.exact);
+            }
+        }
+    }
+
+    #[test]
+    fn solves_win_draw_loss() {
+        for start in starts() {
+            for mut game in endgames(&start, 8) {
+                let player = game.to_move();
+                let outcome = brute_force(&mut game, player).signum();
+                assert_eq!(EndgameSolver::wld().search(&game, 1).score, outcome);
+
+                let (best, moves) = EndgameSolver::wld_moves(&game);
+                assert_eq!(best, outcome);
+                for action in game.legal_moves() {
+                    let mv = game.make_move(action);
+                    let value = brute_force(&mut game, player).signum();
+                    game.unmake_move(mv);
+                    assert_eq!(moves.contains(&action), value == outcome, "{}", game);
+                }
+            }
+        }
+    }
+}
@@ -2,6 +2,16 @@ use super::{Evaluation, Heuristic};
 use crate::othello::*;
 use rand::{prelude::ThreadRng, thread_rng, Rng};
 
+/// Heuristics that count discs are turned around under misère rules,
+/// where the player with fewer discs wins.
+fn disc_sign(game: &Othello) -> isize {
+    if game.rules().misere {
+        -1
+    } else {
+        1
+    }
+}
+
 #[derive(Clone)]
 pub struct HZero;
 
@@ -62,10 +72,11 @@ impl Heuristic for HUnit {
                 }
             }
         }
-        sum
+        disc_sign(game) * sum
     }
 }
 
+// The weights of each square on the standard 8x8 board
 const CELL_WEIGHTS: [[isize; 8]; 8] = [
     [120, -20, 20, 5, 5, 20, -20, 120],
     [-20, -40, -5, -5, -5, -5, -40, -20],
@@ -77,35 +88,69 @@ const CELL_WEIGHTS: [[isize; 8]; 8] = [
     [120, -20, 20, 5, 5, 20, -20, 120],
 ];
 
+/// The weight of every square on a board of one size, in row-major order.
 #[derive(Clone)]
-pub struct HWeighted;
+struct CellWeights {
+    width: usize,
+    height: usize,
+    weights: Vec<isize>,
+}
+
+impl CellWeights {
+    /// The weights for a board of any size. The 8x8 weights depend only on
+    /// how far a square is from the nearest edge in each direction, so other
+    /// sizes take the weight of the 8x8 square the same distance from the
+    /// edges, with everything further in weighted like the center.
+    fn new(width: usize, height: usize) -> CellWeights {
+        let from_edge = |i: usize, len: usize| i.min(len - 1 - i).min(3);
+        let weights = (0..width * height)
+            .map(|index| {
+                CELL_WEIGHTS[from_edge(index / width, height)][from_edge(index % width, width)]
+            })
+            .collect();
+
+        CellWeights {
+            width,
+            height,
+            weights,
+        }
+    }
+
+    /// The total weight of the squares in `bits`.
+    fn total(&self, mut bits: Bits) -> isize {
+        let mut total = 0;
+        while bits != 0 {
+            total += self.weights[bits.trailing_zeros() as usize];
+            bits &= bits - 1;
+        }
+        total
+    }
+}
+
+#[derive(Clone)]
+pub struct HWeighted {
+    // Built for the size of the last board evaluated, which hardly ever changes
+    weights: CellWeights,
+}
 
 impl HWeighted {
     pub fn new() -> Box<HWeighted> {
-        Box::new(HWeighted)
+        Box::new(HWeighted {
+            weights: CellWeights::new(8, 8),
+        })
     }
 }
 
 impl Heuristic for HWeighted {
     fn eval(&mut self, game: &Othello, player: Disc) -> Evaluation {
         let board = game.board();
-        let opponent = player.opponent();
-        let mut sum = 0;
-        for row in 0..game.board().height() {
-            for col in 0..game.board().width() {
-                let pos = Position {
-                    row: row as isize,
-                    col: col as isize,
-                };
-                let disc = board.at(pos);
-                if disc == player {
-                    sum += CELL_WEIGHTS[row][col];
-                } else if disc == opponent {
-                    sum -= CELL_WEIGHTS[row][col];
-                }
-            }
+        if (self.weights.width, self.weights.height) != (board.width(), board.height()) {
+            self.weights = CellWeights::new(board.width(), board.height());
         }
-        sum
+
+        let sum = self.weights.total(board.discs(player))
+            - self.weights.total(board.discs(player.opponent()));
+        disc_sign(game) * sum
     }
 }
 
@@ -0,0 +1,99 @@
+use super::{Search, SearchResult};
+use crate::othello::*;
+use std::time::{Duration, Instant};
+
+/// Searches one move deeper at a time until it runs out of time, playing
+/// the best action from the deepest search that finished. Each search
+/// starts with the best action from the one before it.
+pub struct IterativeDeepening {
+    search: Box<dyn Search>,
+    limit: Duration,
+}
+
+impl IterativeDeepening {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(search: Box<dyn Search>, limit: Duration) -> Box<dyn Search> {
+        Box::new(IterativeDeepening { search, limit })
+    }
+}
+
+impl Search for IterativeDeepening {
+    /// Searches up to `depth` deep, or until the time limit runs out. The
+    /// result is the deepest search that finished, with the nodes, table
+    /// hits, researches and time of every search.
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult {
+        let start = Instant::now();
+        let deadline = start + self.limit;
+
+        // The first search always finishes, so there is an action to play
+        self.search.set_deadline(None);
+        self.search.set_first_move(None);
+        let mut best = self.search.search(game, 1);
+        let mut total = best.clone();
+
+        self.search.set_deadline(Some(deadline));
+        for depth in 2..(depth.saturating_add(1)) {
+            // Once every line reaches the end of the game, going deeper changes nothing
+            if best.exact || Instant::now() >= deadline {
+                break;
+            }
+
+            self.search.set_first_move(best.best);
+            let result = self.search.search(game, depth);
+            total.expanded += result.expanded;
+            total.generated += result.generated;
+            total.tt_hits += result.tt_hits;
+            total.tt_cutoffs += result.tt_cutoffs;
+            total.researches += result.researches;
+
+            // A search cut short by the deadline is thrown away
+            if result.cut_short {
+                break;
+            }
+            best = result;
+        }
+
+        self.search.set_deadline(None);
+        self.search.set_first_move(None);
+
+        SearchResult {
+            expanded: total.expanded,
+            generated: total.generated,
+            tt_hits: total.tt_hits,
+            tt_cutoffs: total.tt_cutoffs,
+            researches: total.researches,
+            elapsed: start.elapsed(),
+            ..best
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solve::{AlphaBeta, HWeighted};
+
+    #[test]
+    fn keeps_searches_that_finish() {
+        let game = Othello::new(Board::default());
+        let mut search =
+            IterativeDeepening::new(AlphaBeta::new(HWeighted::new()), Duration::from_secs(600));
+
+        let result = search.search(&game, 4);
+        assert_eq!(result.depth, 4);
+        assert!(!result.cut_short);
+    }
+
+    #[test]
+    fn searches_tell_when_they_are_cut_short() {
+        let game = Othello::new(Board::default());
+        let mut search = AlphaBeta::new(HWeighted::new());
+
+        search.set_deadline(Some(Instant::now()));
+        let result = search.search(&game, 8);
+        assert!(result.cut_short && !result.exact);
+
+        search.set_deadline(None);
+        assert!(!search.search(&game, 3).cut_short);
+    }
+}
@@ -1,48 +1,51 @@
-use super::{Evaluation, Heuristic, Node, Search};
+use super::{Deadline, Evaluation, Heuristic, Line, Node, Search, SearchResult};
 use crate::othello::*;
+use std::time::Instant;
 
 pub struct Minimax {
     heuristic: Box<dyn Heuristic>,
     expanded: usize,
     generated: usize,
+    deadline: Deadline,
+    horizon: bool,
 }
 
 impl Minimax {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(heuristic: Box<dyn Heuristic>) -> Box<dyn Search> {
         Box::new(Minimax {
             heuristic,
             expanded: 0,
             generated: 0,
+            deadline: Deadline::default(),
+            horizon: false,
         })
     }
 
     fn minimax(
         &mut self,
         node: Node,
-        game: &Othello,
+        game: &mut Othello,
         player: Disc,
-        next_move: Disc,
         depth: usize,
-    ) -> (Option<Position>, Evaluation) {
+    ) -> (Line, Evaluation) {
         self.expanded += 1;
 
+        if self.deadline.expired(self.expanded) {
+            return (Line::new(), 0);
+        }
+
         if depth == 0 {
-            return (None, self.heuristic.eval(game, player));
+            self.horizon = true;
+            return (Line::new(), self.heuristic.eval(game, player));
         }
 
         // What possible moves can we make?
-        let moves = self.successors(game, next_move);
-
-        // No possible moves from here, see what moves are in our opponent's future
-        if moves.len() == 0 {
-            let (_, value) = self.minimax(
-                node.opposite(),
-                game,
-                player,
-                next_move.opponent(),
-                depth - 1,
-            );
-            return (None, value);
+        let moves = game.legal_moves();
+
+        // No possible moves for either player, the game is over
+        if moves.is_empty() {
+            return (Line::new(), self.heuristic.eval(game, player));
         }
 
         self.generated += moves.len();
@@ -51,81 +54,71 @@ impl Minimax {
         match node {
             Node::Max => {
                 // Max node -> find the best possible move
-                let mut best_move = None;
-                let mut best_value = std::isize::MIN;
+                let mut best_line = Line::new();
+                let mut best_value = isize::MIN;
 
-                for (node_move, node_game) in moves.iter() {
+                for node_move in moves.iter() {
                     // Recursively call minimax to find the maximum value we can force
-                    let (_, value) = self.minimax(
-                        Node::Min,
-                        node_game,
-                        player,
-                        next_move.opponent(),
-                        depth - 1,
-                    );
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) = self.minimax(Node::Min, game, player, depth - 1);
+                    game.unmake_move(mv);
 
                     // Update local maximum
                     if value > best_value {
-                        best_move = Some(*node_move);
+                        line.push(*node_move);
+                        best_line = line;
                         best_value = value;
                     }
                 }
-                (best_move, best_value)
+                (best_line, best_value)
             }
             Node::Min => {
                 // Min node -> assume opponent will choose the worst possible move for us
-                let mut worst_move = None;
-                let mut worst_value = std::isize::MAX;
+                let mut worst_line = Line::new();
+                let mut worst_value = isize::MAX;
 
-                for (node_move, node_game) in moves.iter() {
+                for node_move in moves.iter() {
                     // Recursively call minimax to find the minimum value they can force
-                    let (_, value) = self.minimax(
-                        Node::Max,
-                        node_game,
-                        player,
-                        next_move.opponent(),
-                        depth - 1,
-                    );
+                    let mv = game.make_move(*node_move);
+                    let (mut line, value) = self.minimax(Node::Max, game, player, depth - 1);
+                    game.unmake_move(mv);
 
                     // Update local minimum
                     if value < worst_value {
-                        worst_move = Some(*node_move);
+                        line.push(*node_move);
+                        worst_line = line;
                         worst_value = value;
                     }
                 }
-                (worst_move, worst_value)
+                (worst_line, worst_value)
             }
         }
     }
-
-    fn successors(&self, game: &Othello, next_move: Disc) -> Vec<(Position, Othello)> {
-        game.valid_moves(next_move)
-            .iter()
-            .map(|m| {
-                let mut successor = game.clone();
-                successor.place(*m, next_move);
-                (*m, successor)
-            })
-            .collect()
-    }
 }
 
 impl Search for Minimax {
-    fn search(
-        &mut self,
-        game: &Othello,
-        player: Disc,
-        depth: usize,
-    ) -> (Option<Position>, Evaluation) {
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult {
+        let start = Instant::now();
         self.expanded = 0;
-        self.minimax(Node::Max, game, player, player, depth)
-    }
-
-    fn nodes_expanded(&self) -> usize {
-        self.expanded
+        self.generated = 0;
+        self.horizon = false;
+
+        let mut game = game.clone();
+        let player = game.to_move();
+        let (line, score) = self.minimax(Node::Max, &mut game, player, depth);
+        SearchResult::new(
+            line,
+            score,
+            depth,
+            self.expanded,
+            self.generated,
+            start,
+            self.horizon,
+            self.deadline.passed(),
+        )
     }
 
-    fn nodes_generated(&self) -> usize {
-        self.generated
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = Deadline::new(deadline);
     }
 }
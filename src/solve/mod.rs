@@ -1,33 +1,374 @@
 mod alphabeta;
 mod ab_order;
+mod ab_order_history;
 mod ab_order_unit;
+mod endgame;
 mod heuristic;
+mod iterative;
 mod minimax;
+mod mtdf;
+mod pvs;
+mod transposition;
 
 pub use alphabeta::*;
 pub use ab_order::*;
+pub use ab_order_history::*;
 pub use ab_order_unit::*;
+pub use endgame::*;
 pub use heuristic::*;
+pub use iterative::*;
 pub use minimax::*;
+pub use mtdf::*;
+pub use pvs::*;
+pub use transposition::*;
 
-use crate::othello::{Disc, Othello, Position};
+use crate::othello::{Action, Disc, Othello};
+use std::time::{Duration, Instant};
 
 pub type Evaluation = isize;
 
+/// What a search found, and what it took to find it.
+#[derive(Clone)]
+pub struct SearchResult {
+    /// The best action for the player to move, unless the game is over.
+    pub best: Option<Action>,
+    pub score: Evaluation,
+    /// The line of play expected from here, starting with `best`.
+    pub pv: Vec<Action>,
+    /// How many moves deep the search went.
+    pub depth: usize,
+    pub expanded: usize,
+    pub generated: usize,
+    /// The number of positions found in the transposition table,
+    /// for searches that have one.
+    pub tt_hits: usize,
+    /// The number of transposition table entries that ended
+    /// the search of their position.
+    pub tt_cutoffs: usize,
+    /// The number of times the root was searched again with a
+    /// different window, for searches that guess at the value.
+    pub researches: usize,
+    pub elapsed: Duration,
+    /// Whether every line was played out to the end of the game,
+    /// so that searching any deeper would make no difference.
+    pub exact: bool,
+    /// Whether the search ran out of time before it finished,
+    /// so that it may have missed a better line.
+    pub cut_short: bool,
+}
+
+impl SearchResult {
+    /// The result of a search that stopped at the `horizon`
+    /// on some line, or was `cut_short` by its deadline.
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        line: Line,
+        score: Evaluation,
+        depth: usize,
+        expanded: usize,
+        generated: usize,
+        start: Instant,
+        horizon: bool,
+        cut_short: bool,
+    ) -> SearchResult {
+        let mut pv = line;
+        pv.reverse();
+        SearchResult {
+            best: pv.first().copied(),
+            score,
+            pv,
+            depth,
+            expanded,
+            generated,
+            tt_hits: 0,
+            tt_cutoffs: 0,
+            researches: 0,
+            elapsed: start.elapsed(),
+            exact: !horizon && !cut_short,
+            cut_short,
+        }
+    }
+}
+
+/// A line of play from the last move back to the first, so that a search
+/// can put its move in front of the line it got back from the move after.
+type Line = Vec<Action>;
+
 pub trait Heuristic {
     fn eval(&mut self, game: &Othello, player: Disc) -> Evaluation;
 }
 
 pub trait Search {
-    fn search(
+    /// Searches for the best action for the player to move in `game`.
+    /// There is no action to return once the game is over.
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult;
+
+    /// Searches like `search`, but only for a value within the window `(a, b)`.
+    /// A value outside the window is only a bound on the true value. Searches
+    /// that don't take a window search with the full one.
+    fn search_window(
         &mut self,
         game: &Othello,
+        depth: usize,
+        _a: Evaluation,
+        _b: Evaluation,
+    ) -> SearchResult {
+        self.search(game, depth)
+    }
+
+    /// Stops searching once `deadline` has passed, after which `search` returns
+    /// as soon as it can with a meaningless result. Searches that can't stop
+    /// early ignore it and always finish.
+    fn set_deadline(&mut self, _deadline: Option<Instant>) {}
+
+    /// Searches `action` first from the root, such as the best action found
+    /// by a shallower search. Searches that don't order moves ignore it.
+    fn set_first_move(&mut self, _action: Option<Action>) {}
+}
+
+/// A time limit on a search. Reading the clock isn't free, so it
+/// is only checked every so many nodes.
+#[derive(Default)]
+struct Deadline {
+    at: Option<Instant>,
+    expired: bool,
+}
+
+impl Deadline {
+    const CHECK_EVERY: usize = 1024;
+
+    fn new(at: Option<Instant>) -> Deadline {
+        Deadline { at, expired: false }
+    }
+
+    /// Whether the deadline has passed, checking the clock if `nodes`
+    /// have been expanded since it was last checked.
+    fn expired(&mut self, nodes: usize) -> bool {
+        if !self.expired && nodes % Deadline::CHECK_EVERY == 0 {
+            self.expired = self.at.is_some_and(|at| Instant::now() >= at);
+        }
+        self.expired
+    }
+
+    /// Whether the deadline was found to have passed.
+    fn passed(&self) -> bool {
+        self.expired
+    }
+}
+
+/// Moves `first` to the front of `moves` if it is one of them,
+/// keeping the rest in order.
+fn promote<T>(moves: &mut [T], first: Option<Action>, action: impl Fn(&T) -> Action) {
+    let index = first.and_then(|first| moves.iter().position(|mv| action(mv) == first));
+    if let Some(index) = index {
+        moves[..=index].rotate_right(1);
+    }
+}
+
+/// Searches with a window of `width` either side of `guess`, opening up
+/// whichever side the value falls outside of and searching again.
+/// Returns the result and the number of times it searched again.
+fn aspiration(
+    guess: Option<Evaluation>,
+    width: Option<Evaluation>,
+    mut search: impl FnMut(Evaluation, Evaluation) -> (Line, Evaluation),
+) -> ((Line, Evaluation), usize) {
+    let (mut a, mut b) = match (guess, width) {
+        (Some(guess), Some(width)) => (guess.saturating_sub(width), guess.saturating_add(width)),
+        _ => (isize::MIN, isize::MAX),
+    };
+
+    let mut researches = 0;
+    loop {
+        let result = search(a, b);
+        if result.1 <= a && a > isize::MIN {
+            a = isize::MIN;
+        } else if result.1 >= b && b < isize::MAX {
+            b = isize::MAX;
+        } else {
+            return (result, researches);
+        }
+        researches += 1;
+    }
+}
+
+/// What the alpha-beta searches keep track of within and between searches:
+/// node counts, the deadline, the move to search first, an optional
+/// transposition table and the aspiration window around the last value.
+struct Tree {
+    expanded: usize,
+    generated: usize,
+    deadline: Deadline,
+    horizon: bool,
+    first_move: Option<Action>,
+    table: Option<TranspositionTable>,
+    aspiration: Option<Evaluation>,
+    previous: Option<Evaluation>,
+    researches: usize,
+}
+
+/// What the transposition table had to say about a position.
+enum Probe {
+    /// The value of the position, without searching it any further.
+    Cutoff(Line, Evaluation),
+    /// The move to search first, if there is one.
+    First(Option<Action>),
+}
+
+impl Tree {
+    fn new(table: Option<TranspositionTable>, aspiration: Option<Evaluation>) -> Tree {
+        Tree {
+            expanded: 0,
+            generated: 0,
+            deadline: Deadline::default(),
+            horizon: false,
+            first_move: None,
+            table,
+            aspiration,
+            previous: None,
+            researches: 0,
+        }
+    }
+
+    /// Resets the counts for a new search.
+    fn start(&mut self) {
+        self.expanded = 0;
+        self.generated = 0;
+        self.researches = 0;
+        if let Some(table) = &mut self.table {
+            table.new_search();
+        }
+    }
+
+    /// Looks up `game` in the table, if there is one. The move to search first
+    /// is the one given to `set_first_move`, or else the table's best move.
+    fn probe(
+        &mut self,
+        game: &Othello,
+        player: Disc,
+        depth: usize,
+        a: Evaluation,
+        b: Evaluation,
+    ) -> Probe {
+        let first_move = self.first_move.take();
+        if let Some(table) = &mut self.table {
+            if let Some(entry) = table.probe(game, player) {
+                if entry.cuts_off(depth, a, b) {
+                    // There's no telling whether the entry's search reached the end of the game
+                    table.cutoff();
+                    self.horizon = true;
+                    return Probe::Cutoff(entry.best.into_iter().collect(), entry.score);
+                }
+                return Probe::First(first_move.or(entry.best));
+            }
+        }
+        Probe::First(first_move)
+    }
+
+    /// Stores the value of `game` found with the window `(a, b)`, if there is a table.
+    #[allow(clippy::too_many_arguments)]
+    fn store(
+        &mut self,
+        game: &Othello,
+        player: Disc,
+        depth: usize,
+        a: Evaluation,
+        b: Evaluation,
+        line: &Line,
+        value: Evaluation,
+    ) {
+        // Results from a search cut short by its deadline are meaningless
+        if let Some(table) = &mut self.table {
+            if !self.deadline.passed() {
+                let bound = Bound::of(value, a, b);
+                table.store(game, player, depth, value, bound, line.last().copied());
+            }
+        }
+    }
+
+    fn result(&self, line: Line, score: Evaluation, depth: usize, start: Instant) -> SearchResult {
+        let mut result = SearchResult::new(
+            line,
+            score,
+            depth,
+            self.expanded,
+            self.generated,
+            start,
+            self.horizon,
+            self.deadline.passed(),
+        );
+        if let Some(table) = &self.table {
+            result.tt_hits = table.hits();
+            result.tt_cutoffs = table.cutoffs();
+        }
+        result.researches = self.researches;
+        result
+    }
+}
+
+/// An alpha-beta search that keeps its state in a `Tree`, so that
+/// `search_tree` can search its root with any window.
+trait TreeSearch {
+    fn tree(&mut self) -> &mut Tree;
+
+    /// Searches `game` from the root with the window `(a, b)`.
+    fn root(
+        &mut self,
+        game: &mut Othello,
         player: Disc,
         depth: usize,
-    ) -> (Option<Position>, Evaluation);
+        a: Evaluation,
+        b: Evaluation,
+    ) -> (Line, Evaluation);
+}
+
+/// Searches the root of `game` with the search's aspiration window
+/// around the value found by its last search, if it has one.
+fn search_tree(search: &mut impl TreeSearch, game: &Othello, depth: usize) -> SearchResult {
+    let start = Instant::now();
+    let tree = search.tree();
+    tree.start();
+
+    // Every search of the root starts with the same move
+    let (guess, width, first_move) = (tree.previous, tree.aspiration, tree.first_move);
+    let ((line, score), researches) = aspiration(guess, width, |a, b| {
+        search.tree().first_move = first_move;
+        search_root(search, game, depth, a, b)
+    });
+
+    let tree = search.tree();
+    tree.researches = researches;
+    if !tree.deadline.passed() {
+        tree.previous = Some(score);
+    }
+    tree.result(line, score, depth, start)
+}
 
-    fn nodes_expanded(&self) -> usize;
-    fn nodes_generated(&self) -> usize;
+/// Searches the root of `game` once with the window `(a, b)`.
+fn search_tree_window(
+    search: &mut impl TreeSearch,
+    game: &Othello,
+    depth: usize,
+    a: Evaluation,
+    b: Evaluation,
+) -> SearchResult {
+    let start = Instant::now();
+    search.tree().start();
+    let (line, score) = search_root(search, game, depth, a, b);
+    search.tree().result(line, score, depth, start)
+}
+
+fn search_root(
+    search: &mut impl TreeSearch,
+    game: &Othello,
+    depth: usize,
+    a: Evaluation,
+    b: Evaluation,
+) -> (Line, Evaluation) {
+    search.tree().horizon = false;
+    let mut game = game.clone();
+    let player = game.to_move();
+    search.root(&mut game, player, depth, a, b)
 }
 
 #[derive(Copy, Clone)]
@@ -36,11 +377,73 @@ enum Node {
     Min,
 }
 
-impl Node {
-    fn opposite(&self) -> Node {
-        match self {
-            Node::Min => Node::Max,
-            Node::Max => Node::Min,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::othello::Board;
+
+    const DEPTH: usize = 5;
+
+    /// Positions from the opening to the endgame of games
+    /// played with moves picked by the ply they are played on.
+    fn positions() -> Vec<Othello> {
+        let mut positions = Vec::new();
+        for seed in 1..4 {
+            let mut game = Othello::new(Board::default());
+            let mut ply = 0;
+            while !game.is_game_over() {
+                if ply % 9 == seed {
+                    positions.push(game.clone());
+                }
+                let moves = game.legal_moves();
+                game.play(moves[(ply * seed + seed / 2) % moves.len()]);
+                ply += 1;
+            }
         }
+        positions
+    }
+
+    /// Checks that searches made by `new` score every position the same as
+    /// plain alpha-beta. Each one searches a move shallower first, so that
+    /// it has a guess, a table and a first move to start from.
+    fn assert_same_scores(new: impl Fn() -> Box<dyn Search>) {
+        let mut alphabeta = AlphaBeta::new(HWeighted::new());
+        for game in positions() {
+            let mut search = new();
+            for depth in DEPTH - 1..=DEPTH {
+                let expected = alphabeta.search(&game, depth).score;
+                let result = search.search(&game, depth);
+                assert_eq!(result.score, expected, "{} at depth {}", game, depth);
+                search.set_first_move(result.best);
+            }
+        }
+    }
+
+    #[test]
+    fn transposition_tables_keep_scores() {
+        let table = || Some(TranspositionTable::new(1));
+        assert_same_scores(|| AlphaBeta::with_options(HWeighted::new(), table(), None));
+        assert_same_scores(|| AlphaBetaOrdering::with_options(HWeighted::new(), table(), None));
+    }
+
+    #[test]
+    fn principal_variation_search_keeps_scores() {
+        assert_same_scores(|| PrincipalVariation::new(HWeighted::new()));
+    }
+
+    #[test]
+    fn mtdf_and_aspiration_windows_keep_scores() {
+        assert_same_scores(|| {
+            let table = Some(TranspositionTable::new(1));
+            let search = AlphaBetaOrdering::with_options(HWeighted::new(), table, None);
+            Mtdf::new(search)
+        });
+        assert_same_scores(|| AlphaBeta::with_options(HWeighted::new(), None, Some(1)));
+        assert_same_scores(|| AlphaBetaOrdering::with_options(HWeighted::new(), None, Some(10)));
+    }
+
+    #[test]
+    fn history_ordering_keeps_scores() {
+        assert_same_scores(|| AlphaBetaOrderingHistory::new(HWeighted::new()));
     }
 }
@@ -0,0 +1,94 @@
+use super::{Evaluation, Search, SearchResult};
+use crate::othello::*;
+use std::time::Instant;
+
+/// MTD(f): closes in on the value of a position with a series of null-window
+/// searches, starting from the value found by the last search. The inner
+/// search should have a transposition table, or it searches the same
+/// positions over and over.
+pub struct Mtdf {
+    search: Box<dyn Search>,
+    guess: Evaluation,
+}
+
+impl Mtdf {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(search: Box<dyn Search>) -> Box<dyn Search> {
+        Box::new(Mtdf { search, guess: 0 })
+    }
+}
+
+impl Search for Mtdf {
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult {
+        let start = Instant::now();
+
+        let mut value = self.guess;
+        let mut lower = isize::MIN;
+        let mut upper = isize::MAX;
+        let mut pv = Vec::new();
+        let mut failed_high = false;
+        let mut expanded = 0;
+        let mut generated = 0;
+        let mut tt_hits = 0;
+        let mut tt_cutoffs = 0;
+        let mut exact = true;
+        let mut searches = 0;
+        let mut cut_short = false;
+
+        while lower < upper {
+            let beta = if value == lower { value + 1 } else { value };
+            let result = self.search.search_window(game, depth, beta - 1, beta);
+            expanded += result.expanded;
+            generated += result.generated;
+            exact &= result.exact;
+            tt_hits += result.tt_hits;
+            tt_cutoffs += result.tt_cutoffs;
+            searches += 1;
+
+            // Only a search that failed high has found a line at least as good as its value
+            value = result.score;
+            if value < beta {
+                upper = value;
+                if !failed_high {
+                    pv = result.pv;
+                }
+            } else {
+                lower = value;
+                pv = result.pv;
+                failed_high = true;
+            }
+
+            cut_short = result.cut_short;
+            if cut_short {
+                break;
+            }
+        }
+
+        if !cut_short {
+            self.guess = value;
+        }
+
+        SearchResult {
+            best: pv.first().copied(),
+            score: value,
+            pv,
+            depth,
+            expanded,
+            generated,
+            tt_hits,
+            tt_cutoffs,
+            researches: searches - 1,
+            elapsed: start.elapsed(),
+            exact: exact && !cut_short,
+            cut_short,
+        }
+    }
+
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.search.set_deadline(deadline);
+    }
+
+    fn set_first_move(&mut self, action: Option<Action>) {
+        self.search.set_first_move(action);
+    }
+}
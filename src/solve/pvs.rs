@@ -0,0 +1,200 @@
+use super::{
+    ab_order::ordered_successors, promote, Deadline, Evaluation, Heuristic, Line, Node, Search,
+    SearchResult,
+};
+use crate::othello::*;
+use std::time::Instant;
+
+/// Principal variation search (NegaScout): alpha-beta with move ordering
+/// that searches the first move with the full window, then only proves the
+/// rest are no better with a null window, searching them again if they are.
+pub struct PrincipalVariation {
+    heuristic: Box<dyn Heuristic>,
+    expanded: usize,
+    generated: usize,
+    deadline: Deadline,
+    horizon: bool,
+    first_move: Option<Action>,
+}
+
+impl PrincipalVariation {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(heuristic: Box<dyn Heuristic>) -> Box<dyn Search> {
+        Box::new(PrincipalVariation {
+            heuristic,
+            expanded: 0,
+            generated: 0,
+            deadline: Deadline::default(),
+            horizon: false,
+            first_move: None,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn pvs(
+        &mut self,
+        node: Node,
+        game: &mut Othello,
+        player: Disc,
+        depth: usize,
+        a: Evaluation,
+        b: Evaluation,
+        this_eval: Evaluation,
+    ) -> (Line, Evaluation) {
+        self.expanded += 1;
+
+        if self.deadline.expired(self.expanded) {
+            return (Line::new(), 0);
+        }
+
+        if depth == 0 {
+            self.horizon = true;
+            return (Line::new(), this_eval);
+        }
+
+        // What possible moves can we make?
+        let mut moves = ordered_successors(self.heuristic.as_mut(), node, game, player);
+
+        // No possible moves for either player, the game is over
+        if moves.is_empty() {
+            return (Line::new(), this_eval);
+        }
+
+        promote(&mut moves, self.first_move.take(), |(mv, _)| *mv);
+        self.generated += moves.len();
+
+        match node {
+            Node::Max => {
+                let mut best_line = Line::new();
+                let mut best_value = isize::MIN;
+                let mut alpha = a;
+
+                for (index, (node_move, node_eval)) in moves.iter().enumerate() {
+                    let mv = game.make_move(*node_move);
+                    let (mut line, mut value) = if index == 0 {
+                        self.pvs(Node::Min, game, player, depth - 1, alpha, b, *node_eval)
+                    } else {
+                        // Prove this move is no better than the best so far
+                        self.pvs(
+                            Node::Min,
+                            game,
+                            player,
+                            depth - 1,
+                            alpha,
+                            alpha + 1,
+                            *node_eval,
+                        )
+                    };
+
+                    // It is better, so find out by how much
+                    if index > 0 && value > alpha && value < b {
+                        (line, value) =
+                            self.pvs(Node::Min, game, player, depth - 1, value, b, *node_eval);
+                    }
+                    game.unmake_move(mv);
+
+                    if value > best_value {
+                        line.push(*node_move);
+                        best_line = line;
+                        best_value = value;
+                    }
+
+                    if best_value > alpha {
+                        alpha = best_value;
+                    }
+
+                    if alpha >= b {
+                        break;
+                    }
+                }
+
+                (best_line, best_value)
+            }
+            Node::Min => {
+                let mut worst_line = Line::new();
+                let mut worst_value = isize::MAX;
+                let mut beta = b;
+
+                for (index, (node_move, node_eval)) in moves.iter().enumerate() {
+                    let mv = game.make_move(*node_move);
+                    let (mut line, mut value) = if index == 0 {
+                        self.pvs(Node::Max, game, player, depth - 1, a, beta, *node_eval)
+                    } else {
+                        // Prove this move is no worse for us than the worst so far
+                        self.pvs(
+                            Node::Max,
+                            game,
+                            player,
+                            depth - 1,
+                            beta - 1,
+                            beta,
+                            *node_eval,
+                        )
+                    };
+
+                    // It is worse, so find out by how much
+                    if index > 0 && value < beta && value > a {
+                        (line, value) =
+                            self.pvs(Node::Max, game, player, depth - 1, a, value, *node_eval);
+                    }
+                    game.unmake_move(mv);
+
+                    if value < worst_value {
+                        line.push(*node_move);
+                        worst_line = line;
+                        worst_value = value;
+                    }
+
+                    if worst_value < beta {
+                        beta = worst_value;
+                    }
+
+                    if a >= beta {
+                        break;
+                    }
+                }
+
+                (worst_line, worst_value)
+            }
+        }
+    }
+}
+
+impl Search for PrincipalVariation {
+    fn search(&mut self, game: &Othello, depth: usize) -> SearchResult {
+        let start = Instant::now();
+        self.expanded = 0;
+        self.generated = 0;
+        self.horizon = false;
+        let mut game = game.clone();
+        let player = game.to_move();
+        let eval = self.heuristic.eval(&game, player);
+        let (line, score) = self.pvs(
+            Node::Max,
+            &mut game,
+            player,
+            depth,
+            isize::MIN,
+            isize::MAX,
+            eval,
+        );
+        SearchResult::new(
+            line,
+            score,
+            depth,
+            self.expanded,
+            self.generated,
+            start,
+            self.horizon,
+            self.deadline.passed(),
+        )
+    }
+
+    fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = Deadline::new(deadline);
+    }
+
+    fn set_first_move(&mut self, action: Option<Action>) {
+        self.first_move = action;
+    }
+}
@@ -0,0 +1,165 @@
+use super::Evaluation;
+use crate::othello::*;
+use std::mem::size_of;
+
+// Mixed into the key of every position searched for white, since heuristic
+// scores from one player's point of view can't be reused for the other
+const WHITE_PERSPECTIVE: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// How a stored score relates to the true value of its position.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The search failed high, so the true value is at least the score.
+    Lower,
+    /// The search failed low, so the true value is at most the score.
+    Upper,
+}
+
+impl Bound {
+    /// The bound on a score found searching with the window `(a, b)`.
+    pub fn of(score: Evaluation, a: Evaluation, b: Evaluation) -> Bound {
+        if score <= a {
+            Bound::Upper
+        } else if score >= b {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Entry {
+    key: u64,
+    generation: usize,
+    pub depth: usize,
+    pub score: Evaluation,
+    pub bound: Bound,
+    pub best: Option<Action>,
+}
+
+impl Entry {
+    /// Whether this entry decides the value of its position when searching
+    /// `depth` deep with the window `(a, b)`, without searching any further.
+    pub fn cuts_off(&self, depth: usize, a: Evaluation, b: Evaluation) -> bool {
+        self.depth >= depth
+            && match self.bound {
+                Bound::Exact => true,
+                Bound::Lower => self.score >= b,
+                Bound::Upper => self.score <= a,
+            }
+    }
+}
+
+/// The largest transposition table that can be asked for, in megabytes.
+pub const MAX_TABLE_MEGABYTES: usize = 4096;
+
+/// A fixed-size table of search results keyed by position hash. When two
+/// positions share a slot, the one searched deeper is kept, unless it was
+/// stored by an earlier search. Its memory is only taken once the first
+/// search starts.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    len: usize,
+    generation: usize,
+    hits: usize,
+    cutoffs: usize,
+}
+
+impl TranspositionTable {
+    /// A table taking up about `megabytes` of memory, up to `MAX_TABLE_MEGABYTES`.
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let bytes = megabytes.min(MAX_TABLE_MEGABYTES).checked_mul(1 << 20);
+        let len = bytes.unwrap_or(0) / size_of::<Option<Entry>>();
+        TranspositionTable {
+            entries: Vec::new(),
+            len: len.max(1),
+            generation: 0,
+            hits: 0,
+            cutoffs: 0,
+        }
+    }
+
+    /// Starts a new search, resetting the hit and cutoff counts.
+    pub fn new_search(&mut self) {
+        if self.entries.is_empty() {
+            self.entries = vec![None; self.len];
+        }
+        self.generation += 1;
+        self.hits = 0;
+        self.cutoffs = 0;
+    }
+
+    /// The entry for `game` searched from `player`'s point of view, if there is one.
+    pub fn probe(&mut self, game: &Othello, player: Disc) -> Option<Entry> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        let key = key(game, player);
+        let entry = self.entries[self.slot(key)].filter(|entry| entry.key == key);
+        if entry.is_some() {
+            self.hits += 1;
+        }
+        entry
+    }
+
+    /// Counts a probed entry that cut off the search.
+    pub fn cutoff(&mut self) {
+        self.cutoffs += 1;
+    }
+
+    pub fn store(
+        &mut self,
+        game: &Othello,
+        player: Disc,
+        depth: usize,
+        score: Evaluation,
+        bound: Bound,
+        best: Option<Action>,
+    ) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let key = key(game, player);
+        let slot = self.slot(key);
+        let replace = match self.entries[slot] {
+            Some(old) => old.generation != self.generation || depth >= old.depth,
+            None => true,
+        };
+
+        if replace {
+            self.entries[slot] = Some(Entry {
+                key,
+                generation: self.generation,
+                depth,
+                score,
+                bound,
+                best,
+            });
+        }
+    }
+
+    /// The number of probes that found an entry since the search started.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// The number of probes that cut off the search since it started.
+    pub fn cutoffs(&self) -> usize {
+        self.cutoffs
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
+
+fn key(game: &Othello, player: Disc) -> u64 {
+    match player {
+        Disc::White => game.hash() ^ WHITE_PERSPECTIVE,
+        _ => game.hash(),
+    }
+}